@@ -0,0 +1,549 @@
+use crate::jep106::Jep106Code;
+use bitfield::bitfield;
+use std::fmt;
+
+bitfield! {
+    /// MEM-AP Control/Status Word register
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Csw(u32);
+    /// Size of the access to perform, see [`Csw::size_bytes`]
+    pub size, _ : 2, 0;
+    /// Address auto-increment and packing mode, see [`AddrInc`]
+    pub addrinc, _ : 5, 4;
+    /// Device enabled (read-only)
+    pub deviceen, _ : 6;
+    /// Transfer in progress (read-only)
+    pub trinprog, _ : 7;
+    /// Mode of operation, 0 is basic mode, 1 is barrier support
+    pub mode, _ : 11, 8;
+    /// Bus type specific transfer type (e.g. AXI domain/cache attributes)
+    pub ty, _ : 15, 12;
+    /// Memory tagging control (ADIv6, overlaps `ty`)
+    pub mte, _ : 15;
+    /// Secure privileged debug enabled (read-only), SDeviceEn in ADIv6
+    pub spiden, _ : 23;
+    /// Bus access protection control, HPROT/AxPROT depending on the bus
+    pub prot, _ : 30, 24;
+    /// AHB: HPROT[0], data access rather than opcode fetch
+    pub hprot_data, _ : 24;
+    /// AHB: HPROT[1], privileged access
+    pub hprot_privileged, _ : 25;
+    /// AHB: HPROT[2], bufferable access
+    pub hprot_bufferable, _ : 26;
+    /// AHB: HPROT[3], cacheable access
+    pub hprot_cacheable, _ : 27;
+    /// AHB5: HNONSEC, non-secure access
+    pub hnonsec, _ : 30;
+    /// AXI: AxCACHE[3:0], bufferable, modifiable, read and write allocate
+    pub axcache, _ : 27, 24;
+    /// AXI and APB4/APB5: AxPROT[0]/PPROT[0], privileged access
+    pub prot_privileged, _ : 28;
+    /// AXI and APB4/APB5: AxPROT[1]/PPROT[1], non-secure access
+    pub prot_nonsec, _ : 29;
+    /// AXI and APB4/APB5: AxPROT[2]/PPROT[2], instruction rather than data
+    /// access
+    pub prot_instruction, _ : 30;
+    /// Debug software access enable
+    pub dbgswenable, _ : 31;
+}
+
+impl Csw {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "CSW";
+
+    /// Size of each access in bytes, `None` for reserved encodings
+    pub fn size_bytes(&self) -> Option<u32> {
+        match self.size() {
+            size @ 0..=5 => Some(1 << size),
+            _ => None,
+        }
+    }
+
+    pub fn addr_inc(&self) -> AddrInc {
+        match self.addrinc() {
+            0 => AddrInc::Off,
+            1 => AddrInc::Single,
+            2 => AddrInc::Packed,
+            _ => AddrInc::Reserved,
+        }
+    }
+
+    /// TAR increment after a DRW access, if auto-increment is enabled
+    pub fn tar_increment(&self) -> Option<u32> {
+        match self.addr_inc() {
+            AddrInc::Single => self.size_bytes(),
+            // Packed transfers always move a full word through DRW
+            AddrInc::Packed => Some(4),
+            AddrInc::Off | AddrInc::Reserved => None,
+        }
+    }
+
+    /// Reserved or unusual field combinations, worth flagging when seen
+    pub fn warnings(&self) -> Vec<&'static str> {
+        let mut warnings = Vec::new();
+        match self.size_bytes() {
+            None => warnings.push("reserved Size encoding"),
+            Some(bytes) if bytes > 4 => {
+                warnings.push("Size wider than 32 bits (large data extension)")
+            }
+            _ => (),
+        }
+        if self.addr_inc() == AddrInc::Reserved {
+            warnings.push("reserved AddrInc encoding");
+        }
+        if self.addr_inc() == AddrInc::Packed && self.size_bytes() == Some(4) {
+            warnings.push("packed transfers with a 32-bit Size behave like single increment");
+        }
+        if self.mode() > 1 {
+            warnings.push("reserved Mode encoding");
+        }
+        warnings
+    }
+
+    /// The PROT bits as named by the bus of the MEM-AP, `None` for an unknown
+    /// bus
+    pub fn prot_attributes(&self, bus: MemApType) -> Option<Vec<&'static str>> {
+        let attributes = match bus {
+            MemApType::Ahb3 | MemApType::Ahb5 | MemApType::Ahb5Hprot => vec![
+                (self.hprot_data(), "data", "opcode"),
+                (self.hprot_privileged(), "priv", "user"),
+                (self.hprot_bufferable(), "buf", ""),
+                (self.hprot_cacheable(), "cache", ""),
+                (self.hnonsec(), "nonsec", ""),
+            ],
+            MemApType::Axi3Axi4 | MemApType::Axi5 => vec![
+                (self.prot_privileged(), "priv", "user"),
+                (self.prot_nonsec(), "nonsec", ""),
+                (self.prot_instruction(), "opcode", "data"),
+                (self.axcache() & 0b0001 != 0, "buf", ""),
+                (self.axcache() & 0b0010 != 0, "modifiable", ""),
+                (self.axcache() & 0b0100 != 0, "ralloc", ""),
+                (self.axcache() & 0b1000 != 0, "walloc", ""),
+            ],
+            MemApType::Apb4Apb5 => vec![
+                (self.prot_privileged(), "priv", "user"),
+                (self.prot_nonsec(), "nonsec", ""),
+                (self.prot_instruction(), "opcode", "data"),
+            ],
+            // APB2 and APB3 have no protection control
+            MemApType::Apb2Apb3 => vec![],
+            MemApType::Unknown(_) => return None,
+        };
+        Some(
+            attributes
+                .into_iter()
+                .map(|(set, on, off)| if set { on } else { off })
+                .filter(|s| !s.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Show the CSW with its PROT bits named for a MEM-AP bus, only the raw
+    /// PROT value is shown for an unknown bus
+    pub fn display(&self, bus: Option<MemApType>) -> CswDisplay {
+        CswDisplay { csw: *self, bus }
+    }
+}
+
+impl fmt::Display for Csw {
+    /// Shows PROT as a raw value, see [`Csw::display`] for naming its bits
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(None).fmt(f)
+    }
+}
+
+/// CSW with its PROT bits named for the bus of the MEM-AP, from [`Csw::display`]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CswDisplay {
+    csw: Csw,
+    bus: Option<MemApType>,
+}
+
+impl fmt::Display for CswDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let csw = self.csw;
+        match csw.size_bytes() {
+            Some(bytes) => write!(f, "SIZE:{}", bytes * 8)?,
+            None => write!(f, "SIZE:reserved({})", csw.size())?,
+        }
+        write!(
+            f,
+            " ADDRINC:{} DEVICEEN:{} TRINPROG:{} MODE:{} TYPE:{:X} MTE:{} SPIDEN:{} PROT:{:02X}",
+            csw.addr_inc(),
+            csw.deviceen() as u8,
+            csw.trinprog() as u8,
+            csw.mode(),
+            csw.ty(),
+            csw.mte() as u8,
+            csw.spiden() as u8,
+            csw.prot(),
+        )?;
+        let attributes = self.bus.and_then(|bus| csw.prot_attributes(bus));
+        if let Some(attributes) = attributes.filter(|attributes| !attributes.is_empty()) {
+            write!(f, " ({})", attributes.join(","))?;
+        }
+        write!(f, " DBGSWEN:{}", csw.dbgswenable() as u8)
+    }
+}
+
+/// CSW address auto-increment and packing mode
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum AddrInc {
+    Off,
+    Single,
+    Packed,
+    Reserved,
+}
+
+impl fmt::Display for AddrInc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AddrInc::Off => "off",
+            AddrInc::Single => "single",
+            AddrInc::Packed => "packed",
+            AddrInc::Reserved => "reserved",
+        })
+    }
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Tar(u32);
+    pub addr, set_addr : 31, 0;
+}
+
+impl Tar {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "TAR";
+
+    /// Auto-increment only applies to TAR[9:0], the address wraps at 1KB
+    /// boundaries
+    pub const AUTO_INC_MASK: u32 = 0x3FF;
+
+    /// The TAR value after an auto-increment
+    pub fn incremented(&self, increment: u32) -> Tar {
+        let addr = self.addr();
+        Tar((addr & !Self::AUTO_INC_MASK) | (addr.wrapping_add(increment) & Self::AUTO_INC_MASK))
+    }
+
+    /// Target address of a banked data register access, BD0-BD3 map to
+    /// TAR[31:4] plus the register offset
+    pub fn banked_addr(&self, bd_index: u8) -> u32 {
+        (self.addr() & !0xF) | (u32::from(bd_index) << 2)
+    }
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Drw(u32);
+}
+
+impl Drw {
+    pub const ADDRESS: u8 = 0x0C;
+    pub const NAME: &'static str = "DRW";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Bd0(u32);
+}
+
+impl Bd0 {
+    pub const ADDRESS: u8 = 0x10;
+    pub const NAME: &'static str = "BD0";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Bd1(u32);
+}
+
+impl Bd1 {
+    pub const ADDRESS: u8 = 0x14;
+    pub const NAME: &'static str = "BD1";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Bd2(u32);
+}
+
+impl Bd2 {
+    pub const ADDRESS: u8 = 0x18;
+    pub const NAME: &'static str = "BD2";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Bd3(u32);
+}
+
+impl Bd3 {
+    pub const ADDRESS: u8 = 0x1C;
+    pub const NAME: &'static str = "BD3";
+}
+
+bitfield! {
+    /// AP Identification Register, present in every AP
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Idr(u32);
+    /// AP type, interpreted according to the class
+    pub ty, _ : 3, 0;
+    pub variant, _ : 7, 4;
+    pub class, _ : 16, 13;
+    /// JEP106 code of the AP designer, see [`Idr::designer_code`]
+    pub u16, designer, _ : 27, 17;
+    pub revision, _ : 31, 28;
+}
+
+impl Idr {
+    pub const ADDRESS: u8 = 0xFC;
+    pub const NAME: &'static str = "IDR";
+
+    pub fn designer_code(&self) -> Jep106Code {
+        Jep106Code::from_designer(self.designer())
+    }
+
+    pub fn kind(&self) -> ApKind {
+        match (self.class(), self.ty()) {
+            // Vendor specific APs often have no class either
+            (0, 0) if self.designer_code() == Jep106Code::ARM => ApKind::JtagAp,
+            (1, _) => ApKind::ComAp,
+            (8, ty) => ApKind::MemAp(match ty {
+                0x1 => MemApType::Ahb3,
+                0x2 => MemApType::Apb2Apb3,
+                0x4 => MemApType::Axi3Axi4,
+                0x5 => MemApType::Ahb5,
+                0x6 => MemApType::Apb4Apb5,
+                0x7 => MemApType::Axi5,
+                0x8 => MemApType::Ahb5Hprot,
+                ty => MemApType::Unknown(ty as u8),
+            }),
+            (class, ty) => ApKind::Unknown {
+                class: class as u8,
+                ty: ty as u8,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Idr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} REVISION:{} VARIANT:{} DESIGNER:{}",
+            self.kind(),
+            self.revision(),
+            self.variant(),
+            self.designer_code()
+        )
+    }
+}
+
+/// The class and type of an AP, from its IDR
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ApKind {
+    JtagAp,
+    ComAp,
+    MemAp(MemApType),
+    Unknown { class: u8, ty: u8 },
+}
+
+impl ApKind {
+    pub fn is_mem_ap(&self) -> bool {
+        matches!(self, ApKind::MemAp(_))
+    }
+
+    /// The bus of a MEM-AP
+    pub fn mem_ap_type(&self) -> Option<MemApType> {
+        match self {
+            ApKind::MemAp(ty) => Some(*ty),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ApKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApKind::JtagAp => f.write_str("JTAG-AP"),
+            ApKind::ComAp => f.write_str("COM-AP"),
+            ApKind::MemAp(ty) => write!(f, "MEM-AP {}", ty),
+            ApKind::Unknown { class, ty } => write!(f, "AP class:{:X} type:{:X}", class, ty),
+        }
+    }
+}
+
+/// The bus a MEM-AP connects to
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MemApType {
+    Ahb3,
+    Apb2Apb3,
+    Axi3Axi4,
+    Ahb5,
+    Apb4Apb5,
+    Axi5,
+    /// AHB5 with enhanced HPROT
+    Ahb5Hprot,
+    Unknown(u8),
+}
+
+impl fmt::Display for MemApType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemApType::Ahb3 => f.write_str("AHB3"),
+            MemApType::Apb2Apb3 => f.write_str("APB2/APB3"),
+            MemApType::Axi3Axi4 => f.write_str("AXI3/AXI4"),
+            MemApType::Ahb5 => f.write_str("AHB5"),
+            MemApType::Apb4Apb5 => f.write_str("APB4/APB5"),
+            MemApType::Axi5 => f.write_str("AXI5"),
+            MemApType::Ahb5Hprot => f.write_str("AHB5 (enhanced HPROT)"),
+            MemApType::Unknown(ty) => write!(f, "type:{:X}", ty),
+        }
+    }
+}
+
+/// Offset of the MEM-AP registers within an ADIv6 AP, the registers keep
+/// their ADIv5 addresses relative to it (e.g. CSW at 0xD00, IDR at 0xDFC)
+pub const ADIV6_OFFSET: u16 = 0xD00;
+
+/// Name of a MEM-AP register
+pub fn register_name(address: u8) -> Option<&'static str> {
+    Some(match address {
+        Csw::ADDRESS => Csw::NAME,
+        Tar::ADDRESS => Tar::NAME,
+        Drw::ADDRESS => Drw::NAME,
+        Bd0::ADDRESS => Bd0::NAME,
+        Bd1::ADDRESS => Bd1::NAME,
+        Bd2::ADDRESS => Bd2::NAME,
+        Bd3::ADDRESS => Bd3::NAME,
+        Idr::ADDRESS => Idr::NAME,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csw_fields() {
+        let csw = Csw(0x2300_0052);
+        assert_eq!(csw.size_bytes(), Some(4));
+        assert_eq!(csw.addr_inc(), AddrInc::Single);
+        assert!(csw.deviceen());
+        assert!(!csw.trinprog());
+        assert_eq!(csw.mode(), 0);
+        assert_eq!(csw.prot(), 0x23);
+        assert_eq!(csw.tar_increment(), Some(4));
+
+        assert_eq!(Csw(0x0300_0011).size_bytes(), Some(2));
+        assert_eq!(Csw(0x0300_0011).tar_increment(), Some(2));
+        assert_eq!(Csw(0x0300_0020).tar_increment(), Some(4));
+        assert_eq!(Csw(0x0300_0002).tar_increment(), None);
+        assert_eq!(Csw(0x0300_0007).size_bytes(), None);
+    }
+
+    #[test]
+    fn csw_full_decode() {
+        let csw = Csw(0xA280_0140);
+        assert!(csw.dbgswenable());
+        assert!(csw.spiden());
+        assert_eq!(csw.mode(), 1);
+        assert_eq!(csw.size_bytes(), Some(1));
+        assert_eq!(csw.prot(), 0x22);
+        assert!(csw.hprot_privileged());
+        assert!(!csw.hprot_data());
+        assert!(!csw.hnonsec());
+        assert!(csw.warnings().is_empty());
+        assert_eq!(
+            csw.to_string(),
+            "SIZE:8 ADDRINC:off DEVICEEN:1 TRINPROG:0 MODE:1 TYPE:0 MTE:0 SPIDEN:1 PROT:22 DBGSWEN:1"
+        );
+        assert_eq!(
+            csw.display(Some(MemApType::Ahb3)).to_string(),
+            "SIZE:8 ADDRINC:off DEVICEEN:1 TRINPROG:0 MODE:1 TYPE:0 MTE:0 SPIDEN:1 PROT:22 (opcode,priv) DBGSWEN:1"
+        );
+    }
+
+    #[test]
+    fn csw_prot_by_bus() {
+        let csw = Csw(0x6300_0002);
+        assert_eq!(
+            csw.prot_attributes(MemApType::Ahb5),
+            Some(vec!["data", "priv", "nonsec"])
+        );
+        assert_eq!(
+            csw.prot_attributes(MemApType::Axi3Axi4),
+            Some(vec!["user", "nonsec", "opcode", "buf", "modifiable"])
+        );
+        assert_eq!(
+            csw.prot_attributes(MemApType::Apb4Apb5),
+            Some(vec!["user", "nonsec", "opcode"])
+        );
+        assert_eq!(csw.prot_attributes(MemApType::Apb2Apb3), Some(vec![]));
+        assert_eq!(csw.prot_attributes(MemApType::Unknown(3)), None);
+        assert_eq!(
+            csw.display(Some(MemApType::Apb2Apb3)).to_string(),
+            csw.to_string()
+        );
+    }
+
+    #[test]
+    fn csw_warnings() {
+        assert_eq!(Csw(0x2300_0052).warnings(), Vec::<&str>::new());
+        assert_eq!(Csw(0x2300_0006).warnings(), ["reserved Size encoding"]);
+        assert_eq!(
+            Csw(0x2300_0003).warnings(),
+            ["Size wider than 32 bits (large data extension)"]
+        );
+        assert_eq!(Csw(0x2300_0032).warnings(), ["reserved AddrInc encoding"]);
+        assert_eq!(
+            Csw(0x2300_0022).warnings(),
+            ["packed transfers with a 32-bit Size behave like single increment"]
+        );
+        assert_eq!(Csw(0x2300_0202).warnings(), ["reserved Mode encoding"]);
+    }
+
+    #[test]
+    fn idr_decode() {
+        // Cortex-M7 AHB-AP
+        let idr = Idr(0x8477_0001);
+        assert_eq!(idr.revision(), 8);
+        assert_eq!(idr.designer_code(), Jep106Code::ARM);
+        assert_eq!(idr.kind(), ApKind::MemAp(MemApType::Ahb3));
+        assert_eq!(idr.variant(), 0);
+        assert_eq!(
+            idr.to_string(),
+            "MEM-AP AHB3 REVISION:8 VARIANT:0 DESIGNER:ARM Ltd (4:3B)"
+        );
+
+        // APB-AP
+        assert_eq!(Idr(0x5477_0002).kind(), ApKind::MemAp(MemApType::Apb2Apb3));
+        // AHB5-AP
+        assert_eq!(Idr(0x8477_0015).kind(), ApKind::MemAp(MemApType::Ahb5));
+        // Kinetis MDM-AP
+        let mdm = Idr(0x001C_0000);
+        assert_eq!(mdm.kind(), ApKind::Unknown { class: 0, ty: 0 });
+        assert_eq!(mdm.designer_code(), Jep106Code::new(0, 0x0E));
+        // JTAG-AP
+        assert_eq!(Idr(0x0476_0010).kind(), ApKind::JtagAp);
+        // COM-AP
+        assert_eq!(Idr(0x0476_2000).kind(), ApKind::ComAp);
+    }
+
+    #[test]
+    fn tar_auto_increment_wraps_at_1k() {
+        assert_eq!(Tar(0x2000_0000).incremented(4), Tar(0x2000_0004));
+        assert_eq!(Tar(0x2000_03FC).incremented(4), Tar(0x2000_0000));
+        assert_eq!(Tar(0x2000_07FE).incremented(2), Tar(0x2000_0400));
+        assert_eq!(Tar(0xFFFF_FFFC).incremented(4), Tar(0xFFFF_FC00));
+    }
+
+    #[test]
+    fn tar_banked_addr() {
+        assert_eq!(Tar(0xE000_EDF0).banked_addr(0), 0xE000_EDF0);
+        assert_eq!(Tar(0xE000_EDF0).banked_addr(3), 0xE000_EDFC);
+        assert_eq!(Tar(0xE000_EDF8).banked_addr(1), 0xE000_EDF4);
+    }
+}
@@ -0,0 +1,2351 @@
+use crate::parser::Direction;
+use bitfield::bitfield;
+use std::fmt;
+
+/// A decoded memory-mapped ARM debug/system register
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ArmRegister {
+    /// DHCSR as read, with the status bits
+    Dhcsr(Dhcsr),
+    /// DHCSR as written, with the debug key
+    DhcsrWrite(DhcsrWrite),
+    Demcr(Demcr),
+    /// AIRCR as read
+    Aircr(Aircr),
+    /// AIRCR as written, with the vector key
+    AircrWrite(AircrWrite),
+    Dcrsr(Dcrsr),
+    Dcrdr(Dcrdr),
+    FpCtrl(FpCtrl),
+    FpRemap(FpRemap),
+    /// FP_COMPn with its comparator number
+    FpComp(u8, FpComp),
+    DwtCtrl(DwtCtrl),
+    DwtCyccnt(DwtCyccnt),
+    DwtCpicnt(DwtCpicnt),
+    DwtExccnt(DwtExccnt),
+    /// DWT_COMPn with its comparator number
+    DwtComp(u8, DwtComp),
+    /// DWT_MASKn with its comparator number
+    DwtMask(u8, DwtMask),
+    /// DWT_FUNCTIONn with its comparator number
+    DwtFunction(u8, DwtFunction),
+    /// ITM_TERn with its register number
+    ItmTer(u8, ItmTer),
+    ItmTpr(ItmTpr),
+    ItmTcr(ItmTcr),
+    ItmLar(ItmLar),
+    TpiuAcpr(TpiuAcpr),
+    TpiuSppr(TpiuSppr),
+    TpiuFfcr(TpiuFfcr),
+    Cpuid(Cpuid),
+    Vtor(Vtor),
+    Shcsr(Shcsr),
+    Cfsr(Cfsr),
+    Hfsr(Hfsr),
+    Dfsr(Dfsr),
+    Mmfar(Mmfar),
+    Bfar(Bfar),
+    MpuType(MpuType),
+    MpuCtrl(MpuCtrl),
+    MpuRnr(MpuRnr),
+    /// MPU_RBAR or its alias MPU_RBAR_An
+    MpuRbar(u8, MpuRbar),
+    /// MPU_RASR (MPU_RLAR on Armv8-M) or its alias number
+    MpuRasr(u8, MpuRasr),
+    /// MPU_MAIRn, Armv8-M only
+    MpuMair(u8, MpuMair),
+    SauCtrl(SauCtrl),
+    SauType(SauType),
+    SauRnr(SauRnr),
+    SauRbar(SauRbar),
+    SauRlar(SauRlar),
+    Dscsr(Dscsr),
+    Dauthctrl(Dauthctrl),
+    Dauthstatus(Dauthstatus),
+}
+
+impl ArmRegister {
+    /// Decode the value of a memory access, if the address is a known register.
+    /// Registers with keyed writes decode differently for reads and writes
+    pub fn decode(address: u32, value: u32, direction: Direction) -> Option<Self> {
+        let is_read = direction == Direction::Read;
+        Some(match address {
+            Dhcsr::ADDRESS if is_read => ArmRegister::Dhcsr(Dhcsr(value)),
+            Dhcsr::ADDRESS => ArmRegister::DhcsrWrite(DhcsrWrite(value)),
+            Demcr::ADDRESS => ArmRegister::Demcr(Demcr(value)),
+            Aircr::ADDRESS if is_read => ArmRegister::Aircr(Aircr(value)),
+            Aircr::ADDRESS => ArmRegister::AircrWrite(AircrWrite(value)),
+            Dcrsr::ADDRESS => ArmRegister::Dcrsr(Dcrsr(value)),
+            Dcrdr::ADDRESS => ArmRegister::Dcrdr(Dcrdr(value)),
+            FpCtrl::ADDRESS => ArmRegister::FpCtrl(FpCtrl(value)),
+            FpRemap::ADDRESS => ArmRegister::FpRemap(FpRemap(value)),
+            DwtCtrl::ADDRESS => ArmRegister::DwtCtrl(DwtCtrl(value)),
+            DwtCyccnt::ADDRESS => ArmRegister::DwtCyccnt(DwtCyccnt(value)),
+            DwtCpicnt::ADDRESS => ArmRegister::DwtCpicnt(DwtCpicnt(value)),
+            DwtExccnt::ADDRESS => ArmRegister::DwtExccnt(DwtExccnt(value)),
+            ItmTpr::ADDRESS => ArmRegister::ItmTpr(ItmTpr(value)),
+            ItmTcr::ADDRESS => ArmRegister::ItmTcr(ItmTcr(value)),
+            ItmLar::ADDRESS => ArmRegister::ItmLar(ItmLar(value)),
+            TpiuAcpr::ADDRESS => ArmRegister::TpiuAcpr(TpiuAcpr(value)),
+            TpiuSppr::ADDRESS => ArmRegister::TpiuSppr(TpiuSppr(value)),
+            TpiuFfcr::ADDRESS => ArmRegister::TpiuFfcr(TpiuFfcr(value)),
+            Cpuid::ADDRESS => ArmRegister::Cpuid(Cpuid(value)),
+            Vtor::ADDRESS => ArmRegister::Vtor(Vtor(value)),
+            Shcsr::ADDRESS => ArmRegister::Shcsr(Shcsr(value)),
+            Cfsr::ADDRESS => ArmRegister::Cfsr(Cfsr(value)),
+            Hfsr::ADDRESS => ArmRegister::Hfsr(Hfsr(value)),
+            Dfsr::ADDRESS => ArmRegister::Dfsr(Dfsr(value)),
+            Mmfar::ADDRESS => ArmRegister::Mmfar(Mmfar(value)),
+            Bfar::ADDRESS => ArmRegister::Bfar(Bfar(value)),
+            MpuType::ADDRESS => ArmRegister::MpuType(MpuType(value)),
+            MpuCtrl::ADDRESS => ArmRegister::MpuCtrl(MpuCtrl(value)),
+            MpuRnr::ADDRESS => ArmRegister::MpuRnr(MpuRnr(value)),
+            SauCtrl::ADDRESS => ArmRegister::SauCtrl(SauCtrl(value)),
+            SauType::ADDRESS => ArmRegister::SauType(SauType(value)),
+            SauRnr::ADDRESS => ArmRegister::SauRnr(SauRnr(value)),
+            SauRbar::ADDRESS => ArmRegister::SauRbar(SauRbar(value)),
+            SauRlar::ADDRESS => ArmRegister::SauRlar(SauRlar(value)),
+            Dscsr::ADDRESS => ArmRegister::Dscsr(Dscsr(value)),
+            Dauthctrl::ADDRESS => ArmRegister::Dauthctrl(Dauthctrl(value)),
+            Dauthstatus::ADDRESS => ArmRegister::Dauthstatus(Dauthstatus(value)),
+            _ => return Self::decode_indexed(address, value),
+        })
+    }
+
+    /// Decode the registers repeated per comparator
+    fn decode_indexed(address: u32, value: u32) -> Option<Self> {
+        if let Some(n) = FpComp::index(address) {
+            return Some(ArmRegister::FpComp(n, FpComp(value)));
+        }
+        if let Some(n) = ItmTer::index(address) {
+            return Some(ArmRegister::ItmTer(n, ItmTer(value)));
+        }
+        if let Some(n) = MpuRbar::alias(address) {
+            return Some(ArmRegister::MpuRbar(n, MpuRbar(value)));
+        }
+        if let Some(n) = MpuRasr::alias(address) {
+            return Some(ArmRegister::MpuRasr(n, MpuRasr(value)));
+        }
+        if let Some(n) = MpuMair::index(address) {
+            return Some(ArmRegister::MpuMair(n, MpuMair(value)));
+        }
+        let offset = address.checked_sub(DwtComp::ADDRESS)?;
+        let n = offset / DwtComp::STRIDE;
+        if n >= DwtComp::COUNT {
+            return None;
+        }
+        let n = n as u8;
+        Some(match address {
+            a if a == DwtComp::address(n) => ArmRegister::DwtComp(n, DwtComp(value)),
+            a if a == DwtMask::address(n) => ArmRegister::DwtMask(n, DwtMask(value)),
+            a if a == DwtFunction::address(n) => ArmRegister::DwtFunction(n, DwtFunction(value)),
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArmRegister::Dhcsr(_) | ArmRegister::DhcsrWrite(_) => Dhcsr::NAME,
+            ArmRegister::Demcr(_) => Demcr::NAME,
+            ArmRegister::Aircr(_) | ArmRegister::AircrWrite(_) => Aircr::NAME,
+            ArmRegister::Dcrsr(_) => Dcrsr::NAME,
+            ArmRegister::Dcrdr(_) => Dcrdr::NAME,
+            ArmRegister::FpCtrl(_) => FpCtrl::NAME,
+            ArmRegister::FpRemap(_) => FpRemap::NAME,
+            ArmRegister::FpComp(..) => FpComp::NAME,
+            ArmRegister::DwtCtrl(_) => DwtCtrl::NAME,
+            ArmRegister::DwtCyccnt(_) => DwtCyccnt::NAME,
+            ArmRegister::DwtCpicnt(_) => DwtCpicnt::NAME,
+            ArmRegister::DwtExccnt(_) => DwtExccnt::NAME,
+            ArmRegister::DwtComp(..) => DwtComp::NAME,
+            ArmRegister::DwtMask(..) => DwtMask::NAME,
+            ArmRegister::DwtFunction(..) => DwtFunction::NAME,
+            ArmRegister::ItmTer(..) => ItmTer::NAME,
+            ArmRegister::ItmTpr(_) => ItmTpr::NAME,
+            ArmRegister::ItmTcr(_) => ItmTcr::NAME,
+            ArmRegister::ItmLar(_) => ItmLar::NAME,
+            ArmRegister::TpiuAcpr(_) => TpiuAcpr::NAME,
+            ArmRegister::TpiuSppr(_) => TpiuSppr::NAME,
+            ArmRegister::TpiuFfcr(_) => TpiuFfcr::NAME,
+            ArmRegister::Cpuid(_) => Cpuid::NAME,
+            ArmRegister::Vtor(_) => Vtor::NAME,
+            ArmRegister::Shcsr(_) => Shcsr::NAME,
+            ArmRegister::Cfsr(_) => Cfsr::NAME,
+            ArmRegister::Hfsr(_) => Hfsr::NAME,
+            ArmRegister::Dfsr(_) => Dfsr::NAME,
+            ArmRegister::Mmfar(_) => Mmfar::NAME,
+            ArmRegister::Bfar(_) => Bfar::NAME,
+            ArmRegister::MpuType(_) => MpuType::NAME,
+            ArmRegister::MpuCtrl(_) => MpuCtrl::NAME,
+            ArmRegister::MpuRnr(_) => MpuRnr::NAME,
+            ArmRegister::MpuRbar(..) => MpuRbar::NAME,
+            ArmRegister::MpuRasr(_, r) => r.name(),
+            ArmRegister::MpuMair(..) => MpuMair::NAME,
+            ArmRegister::SauCtrl(_) => SauCtrl::NAME,
+            ArmRegister::SauType(_) => SauType::NAME,
+            ArmRegister::SauRnr(_) => SauRnr::NAME,
+            ArmRegister::SauRbar(_) => SauRbar::NAME,
+            ArmRegister::SauRlar(_) => SauRlar::NAME,
+            ArmRegister::Dscsr(_) => Dscsr::NAME,
+            ArmRegister::Dauthctrl(_) => Dauthctrl::NAME,
+            ArmRegister::Dauthstatus(_) => Dauthstatus::NAME,
+        }
+    }
+}
+
+/// Displays the minimal set of interesting fields
+impl fmt::Display for ArmRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArmRegister::Dhcsr(r) => r.fmt(f),
+            ArmRegister::DhcsrWrite(r) => r.fmt(f),
+            ArmRegister::Demcr(r) => r.fmt(f),
+            ArmRegister::Aircr(r) => r.fmt(f),
+            ArmRegister::AircrWrite(r) => r.fmt(f),
+            ArmRegister::Dcrsr(r) => r.fmt(f),
+            ArmRegister::Dcrdr(r) => r.fmt(f),
+            ArmRegister::FpCtrl(r) => r.fmt(f),
+            ArmRegister::FpRemap(r) => r.fmt(f),
+            ArmRegister::FpComp(n, r) => write!(f, "(n:{}, {})", n, r.fields()),
+            ArmRegister::DwtCtrl(r) => r.fmt(f),
+            ArmRegister::DwtCyccnt(r) => r.fmt(f),
+            ArmRegister::DwtCpicnt(r) => r.fmt(f),
+            ArmRegister::DwtExccnt(r) => r.fmt(f),
+            ArmRegister::DwtComp(n, r) => write!(f, "(n:{}, comp:{:08X})", n, r.0),
+            ArmRegister::DwtMask(n, r) => write!(f, "(n:{}, mask:{})", n, r.mask()),
+            ArmRegister::DwtFunction(n, r) => write!(f, "(n:{}, {})", n, r.fields()),
+            ArmRegister::ItmTer(n, r) => write!(f, "(n:{}, stimena:{:08X})", n, r.0),
+            ArmRegister::ItmTpr(r) => r.fmt(f),
+            ArmRegister::ItmTcr(r) => r.fmt(f),
+            ArmRegister::ItmLar(r) => r.fmt(f),
+            ArmRegister::TpiuAcpr(r) => r.fmt(f),
+            ArmRegister::TpiuSppr(r) => r.fmt(f),
+            ArmRegister::TpiuFfcr(r) => r.fmt(f),
+            ArmRegister::Cpuid(r) => r.fmt(f),
+            ArmRegister::Vtor(r) => r.fmt(f),
+            ArmRegister::Shcsr(r) => r.fmt(f),
+            ArmRegister::Cfsr(r) => r.fmt(f),
+            ArmRegister::Hfsr(r) => r.fmt(f),
+            ArmRegister::Dfsr(r) => r.fmt(f),
+            ArmRegister::Mmfar(r) => r.fmt(f),
+            ArmRegister::Bfar(r) => r.fmt(f),
+            ArmRegister::MpuType(r) => r.fmt(f),
+            ArmRegister::MpuCtrl(r) => r.fmt(f),
+            ArmRegister::MpuRnr(r) => write!(f, "(region:{})", r.region()),
+            ArmRegister::MpuRbar(n, r) => write!(f, "(alias:{}, {})", n, r.fields()),
+            ArmRegister::MpuRasr(n, r) => write!(f, "(alias:{}, {})", n, r.fields()),
+            ArmRegister::MpuMair(n, r) => write!(f, "(n:{}, attrs:{:08X})", n, r.0),
+            ArmRegister::SauCtrl(r) => r.fmt(f),
+            ArmRegister::SauType(r) => write!(f, "(sregion:{})", r.sregion()),
+            ArmRegister::SauRnr(r) => write!(f, "(region:{})", r.region()),
+            ArmRegister::SauRbar(r) => write!(f, "(baddr:{:08X})", r.address()),
+            ArmRegister::SauRlar(r) => r.fmt(f),
+            ArmRegister::Dscsr(r) => r.fmt(f),
+            ArmRegister::Dauthctrl(r) => r.fmt(f),
+            ArmRegister::Dauthstatus(r) => r.fmt(f),
+        }
+    }
+}
+
+bitfield! {
+    /// Debug Halting Control and Status Register, DHCSR, as read (see armv7-M Architecture Reference Manual
+    /// C1.6.2 and armv8-M Architecture Reference Manual)
+    ///
+    /// The status bits read back share their bits with the debug key of [`DhcsrWrite`].
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dhcsr(u32);
+    impl Debug;
+    /// Armv8-M: the core left Debug state since the last read
+    pub s_restart_st, _: 26;
+    pub s_reset_st, _: 25;
+    pub s_retire_st, _: 24;
+    /// Armv8-M: Secure debug is enabled
+    pub s_sde, _: 20;
+    pub s_lockup, _: 19;
+    pub s_sleep, _: 18;
+    pub s_halt, _: 17;
+    pub s_regrdy, _: 16;
+    pub c_maskints, set_c_maskints: 3;
+    pub c_step, set_c_step: 2;
+    pub c_halt, set_c_halt: 1;
+    pub c_debugen, set_c_debugen: 0;
+}
+
+impl Dhcsr {
+    pub const ADDRESS: u32 = 0xE000_EDF0;
+    pub const NAME: &'static str = "DHCSR";
+}
+
+impl fmt::Display for Dhcsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(s_restart_st:{}, s_reset_st:{}, s_sde:{}, s_lockup:{}, s_halt:{}, s_regrdy:{}, c_halt:{}, c_debugen:{})",
+            self.s_restart_st() as u8,
+            self.s_reset_st() as u8,
+            self.s_sde() as u8,
+            self.s_lockup() as u8,
+            self.s_halt() as u8,
+            self.s_regrdy() as u8,
+            self.c_halt() as u8,
+            self.c_debugen() as u8,
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Halting Control and Status Register, DHCSR, as written (see armv7-M Architecture Reference
+    /// Manual C1.6.2)
+    ///
+    /// The write is ignored unless [`DhcsrWrite::dbgkey`] is [`DhcsrWrite::DBGKEY`].
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DhcsrWrite(u32);
+    impl Debug;
+    /// Debug key
+    pub u16, dbgkey, set_dbgkey: 31, 16;
+    pub c_snapstall, set_c_snapstall: 5;
+    pub c_maskints, set_c_maskints: 3;
+    pub c_step, set_c_step: 2;
+    pub c_halt, set_c_halt: 1;
+    pub c_debugen, set_c_debugen: 0;
+}
+
+impl DhcsrWrite {
+    pub const DBGKEY: u16 = 0xA05F;
+
+    /// Whether the write takes effect
+    pub fn has_dbgkey(&self) -> bool {
+        self.dbgkey() == Self::DBGKEY
+    }
+}
+
+impl fmt::Display for DhcsrWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.has_dbgkey() {
+            write!(f, "(ignored, dbgkey:{:04X}, ", self.dbgkey())?;
+        } else {
+            f.write_str("(")?;
+        }
+        write!(
+            f,
+            "c_snapstall:{}, c_maskints:{}, c_step:{}, c_halt:{}, c_debugen:{})",
+            self.c_snapstall() as u8,
+            self.c_maskints() as u8,
+            self.c_step() as u8,
+            self.c_halt() as u8,
+            self.c_debugen() as u8,
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Exception and Monitor Control Register, DEMCR (see armv7-M Architecture Reference Manual C1.6.5)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Demcr(u32);
+    impl Debug;
+    /// Global enable for DWT and ITM features
+    pub trcena, set_trcena: 24;
+    /// DebugMonitor semaphore bit
+    pub mon_req, set_mon_req: 19;
+    /// Step the processor?
+    pub mon_step, set_mon_step: 18;
+    /// Sets or clears the pending state of the DebugMonitor exception
+    pub mon_pend, set_mon_pend: 17;
+    /// Enable the DebugMonitor exception
+    pub mon_en, set_mon_en: 16;
+    /// Enable halting debug trap on a HardFault exception
+    pub vc_harderr, set_vc_harderr: 10;
+    /// Enable halting debug trap on a fault occurring during exception entry
+    /// or exception return
+    pub vc_interr, set_vc_interr: 9;
+    /// Enable halting debug trap on a BusFault exception
+    pub vc_buserr, set_vc_buserr: 8;
+    /// Enable halting debug trap on a UsageFault exception caused by a state
+    /// information error, for example an Undefined Instruction exception
+    pub vc_staterr, set_vc_staterr: 7;
+    /// Enable halting debug trap on a UsageFault exception caused by a
+    /// checking error, for example an alignment check error
+    pub vc_chkerr, set_vc_chkerr: 6;
+    /// Enable halting debug trap on a UsageFault caused by an access to a
+    /// Coprocessor
+    pub vc_nocperr, set_vc_nocperr: 5;
+    /// Enable halting debug trap on a MemManage exception.
+    pub vc_mmerr, set_vc_mmerr: 4;
+    /// Enable Reset Vector Catch
+    pub vc_corereset, set_vc_corereset: 0;
+}
+
+impl Demcr {
+    pub const ADDRESS: u32 = 0xE000_EDFC;
+    pub const NAME: &'static str = "DEMCR";
+}
+
+impl fmt::Display for Demcr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(trcena:{}, vc_harderr:{}, vc_corereset:{})",
+            self.trcena() as u8,
+            self.vc_harderr() as u8,
+            self.vc_corereset() as u8,
+        )
+    }
+}
+
+bitfield! {
+    /// Application Interrupt and Reset Control Register, AIRCR (see armv7-M Architecture Reference Manual B3.2.6)
+    ///
+    /// [`Aircr::set_vectkey`] must be called before this register can effectively be written!
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Aircr(u32);
+    impl Debug;
+    /// Vector Key. The value 0x05FA must be written to this register, otherwise
+    /// the register write is UNPREDICTABLE.
+    get_vectkeystat, set_vectkey: 31,16;
+    /// Indicates the memory system data endianness:
+    ///
+    /// `0`: little endian.\
+    /// `1`: big endian.
+    ///
+    /// See Endian support on page A3-44 for more information.
+    pub endianness, set_endianness: 15;
+    /// Priority grouping, indicates the binary point position.
+    ///
+    /// For information about the use of this field see Priority grouping on page B1-527.
+    ///
+    /// This field resets to `0b000`.
+    pub prigroup, set_prigroup: 10,8;
+    /// System Reset Request:
+    ///
+    /// `0`: do not request a reset.\
+    /// `1`: request reset.
+    ///
+    /// Writing `1` to this bit asserts a signal to request a reset by the external
+    /// system. The system components that are reset by this request are
+    /// IMPLEMENTATION DEFINED. A Local reset is required as part of a system
+    /// reset request.
+    ///
+    /// A Local reset clears this bit to `0`.
+    ///
+    /// See Reset management on page B1-208 for more information
+    pub sysresetreq, set_sysresetreq: 2;
+    /// Clears all active state information for fixed and configurable exceptions:
+    ///
+    /// `0`: do not clear state information.\
+    /// `1`: clear state information.
+    ///
+    /// The effect of writing a `1` to this bit if the processor is not halted in Debug
+    /// state is UNPREDICTABLE.
+    pub vectclractive, set_vectclractive: 1;
+    /// Writing `1` to this bit causes a local system reset, see Reset management on page B1-559 for
+    /// more information. This bit self-clears.
+    ///
+    /// The effect of writing a `1` to this bit if the processor is not halted in Debug state is UNPREDICTABLE.
+    ///
+    /// When the processor is halted in Debug state, if a write to the register writes a `1` to both
+    /// VECTRESET and SYSRESETREQ, the behavior is UNPREDICTABLE.
+    ///
+    /// This bit is write only.
+    pub vectreset, set_vectreset: 0;
+}
+
+impl Aircr {
+    pub const ADDRESS: u32 = 0xE000_ED0C;
+    pub const NAME: &'static str = "AIRCR";
+}
+
+impl fmt::Display for Aircr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(endianness:{}, prigroup:{})",
+            self.endianness() as u8,
+            self.prigroup(),
+        )
+    }
+}
+
+bitfield! {
+    /// Application Interrupt and Reset Control Register, AIRCR, as written (see armv7-M Architecture
+    /// Reference Manual B3.2.6)
+    ///
+    /// The write is ignored unless [`AircrWrite::vectkey`] is [`AircrWrite::VECTKEY`].
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct AircrWrite(u32);
+    impl Debug;
+    /// Vector key
+    pub u16, vectkey, set_vectkey: 31, 16;
+    pub prigroup, set_prigroup: 10, 8;
+    pub sysresetreq, set_sysresetreq: 2;
+    pub vectclractive, set_vectclractive: 1;
+    pub vectreset, set_vectreset: 0;
+}
+
+impl AircrWrite {
+    pub const VECTKEY: u16 = 0x05FA;
+
+    /// Whether the write takes effect
+    pub fn has_vectkey(&self) -> bool {
+        self.vectkey() == Self::VECTKEY
+    }
+}
+
+impl fmt::Display for AircrWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.has_vectkey() {
+            write!(f, "(ignored, vectkey:{:04X}, ", self.vectkey())?;
+        } else {
+            f.write_str("(")?;
+        }
+        write!(
+            f,
+            "prigroup:{}, sysresetreq:{}, vectclractive:{}, vectreset:{})",
+            self.prigroup(),
+            self.sysresetreq() as u8,
+            self.vectclractive() as u8,
+            self.vectreset() as u8,
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Core Register Selector Register, DCRSR (see armv7-M Architecture Reference Manual C1.6.3)
+    ///
+    /// Writing it starts a transfer between DCRDR and the selected core register,
+    /// DHCSR.S_REGRDY is set once the transfer completed.
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dcrsr(u32);
+    impl Debug;
+    /// Access type for the transfer:
+    ///
+    /// `0`: read, the core register is copied into DCRDR.\
+    /// `1`: write, DCRDR is copied into the core register.
+    pub regwnr, set_regwnr: 16;
+    /// The core register to transfer, see [`CoreRegister`]
+    pub u8, regsel, set_regsel: 6, 0;
+}
+
+impl Dcrsr {
+    pub const ADDRESS: u32 = 0xE000_EDF4;
+    pub const NAME: &'static str = "DCRSR";
+
+    pub fn core_register(&self) -> CoreRegister {
+        CoreRegister(self.regsel())
+    }
+}
+
+impl fmt::Display for Dcrsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(regwnr:{}, regsel:{})",
+            self.regwnr() as u8,
+            self.core_register()
+        )
+    }
+}
+
+/// Debug Core Register Data Register, DCRDR (see armv7-M Architecture Reference Manual C1.6.4)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Dcrdr(pub u32);
+
+impl Dcrdr {
+    pub const ADDRESS: u32 = 0xE000_EDF8;
+    pub const NAME: &'static str = "DCRDR";
+}
+
+impl fmt::Display for Dcrdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(data:{:08X})", self.0)
+    }
+}
+
+/// A core register, as selected by [`Dcrsr::regsel`] (see armv7-M Architecture
+/// Reference Manual C1.6.3 and armv8-M Architecture Reference Manual)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct CoreRegister(pub u8);
+
+impl CoreRegister {
+    pub const SP: Self = Self(13);
+    pub const LR: Self = Self(14);
+    /// DebugReturnAddress, the address the core resumes execution at
+    pub const PC: Self = Self(15);
+    pub const XPSR: Self = Self(16);
+    pub const MSP: Self = Self(17);
+    pub const PSP: Self = Self(18);
+    /// CONTROL, FAULTMASK, BASEPRI and PRIMASK, one per byte from the top
+    pub const SPECIAL: Self = Self(20);
+    pub const FPSCR: Self = Self(33);
+
+    /// The name of a register other than the general purpose and FP
+    /// registers, `None` for a reserved selector
+    fn special_name(&self) -> Option<&'static str> {
+        Some(match self.0 {
+            13 => "SP",
+            14 => "LR",
+            15 => "PC",
+            16 => "xPSR",
+            17 => "MSP",
+            18 => "PSP",
+            20 => "CONTROL/FAULTMASK/BASEPRI/PRIMASK",
+            // Armv8-M Security and stack limit extensions
+            24 => "MSP_NS",
+            25 => "PSP_NS",
+            26 => "MSP_S",
+            27 => "PSP_S",
+            28 => "MSPLIM_S",
+            29 => "PSPLIM_S",
+            30 => "MSPLIM_NS",
+            31 => "PSPLIM_NS",
+            33 => "FPSCR",
+            34 => "CONTROL_S/FAULTMASK_S/BASEPRI_S/PRIMASK_S",
+            35 => "CONTROL_NS/FAULTMASK_NS/BASEPRI_NS/PRIMASK_NS",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for CoreRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            0..=12 => write!(f, "R{}", self.0),
+            0x40..=0x5F => write!(f, "S{}", self.0 - 0x40),
+            _ => match self.special_name() {
+                Some(name) => f.write_str(name),
+                None => write!(f, "REGSEL:{:02X}", self.0),
+            },
+        }
+    }
+}
+
+bitfield! {
+    /// Flash Patch Control Register, FP_CTRL (see armv7-M Architecture Reference Manual C1.11.3)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct FpCtrl(u32);
+    impl Debug;
+    /// Flash Patch and Breakpoint architecture revision, see [`FpbVersion`]
+    pub u8, rev, _: 31, 28;
+    /// Most significant bits of the number of instruction address comparators
+    pub u8, num_code_hi, _: 14, 12;
+    /// Number of literal address comparators supported
+    pub u8, num_lit, _: 11, 8;
+    /// Least significant bits of the number of instruction address comparators
+    pub u8, num_code_lo, _: 7, 4;
+    /// Must be written as `1` for a write to be effective
+    pub key, set_key: 1;
+    /// Enable the FPB
+    pub enable, set_enable: 0;
+}
+
+impl FpCtrl {
+    pub const ADDRESS: u32 = 0xE000_2000;
+    pub const NAME: &'static str = "FP_CTRL";
+
+    /// Number of instruction address comparators
+    pub fn num_code(&self) -> u8 {
+        self.num_code_hi() << 4 | self.num_code_lo()
+    }
+
+    /// The architecture revision, `None` for a reserved value
+    pub fn version(&self) -> Option<FpbVersion> {
+        match self.rev() {
+            0 => Some(FpbVersion::V1),
+            1 => Some(FpbVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FpCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(rev:{}, num_code:{}, num_lit:{}, key:{}, enable:{})",
+            self.rev(),
+            self.num_code(),
+            self.num_lit(),
+            self.key() as u8,
+            self.enable() as u8,
+        )
+    }
+}
+
+/// Flash Patch and Breakpoint architecture revision, it defines the FP_COMPn format
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum FpbVersion {
+    /// Armv6-M and Armv7-M, comparators match the Code region only
+    V1,
+    /// Armv8-M and Cortex-M7, comparators hold a full breakpoint address
+    V2,
+}
+
+bitfield! {
+    /// Flash Patch Remap Register, FP_REMAP (see armv7-M Architecture Reference Manual C1.11.4)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct FpRemap(u32);
+    impl Debug;
+    /// Remapping is supported
+    pub rmpspt, _: 29;
+    /// Bits 28:5 of the SRAM address of the remap table
+    pub remap, set_remap: 28, 5;
+}
+
+impl FpRemap {
+    pub const ADDRESS: u32 = 0xE000_2004;
+    pub const NAME: &'static str = "FP_REMAP";
+
+    /// SRAM address of the remap table
+    pub fn address(&self) -> u32 {
+        0x2000_0000 | self.remap() << 5
+    }
+}
+
+impl fmt::Display for FpRemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(rmpspt:{}, remap:{:08X})",
+            self.rmpspt() as u8,
+            self.address()
+        )
+    }
+}
+
+bitfield! {
+    /// Flash Patch Comparator Register, FP_COMPn, in both the FPBv1 (see
+    /// armv7-M Architecture Reference Manual C1.11.5) and FPBv2 (see armv8-M
+    /// Architecture Reference Manual) formats
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct FpComp(u32);
+    impl Debug;
+    /// v1: Breakpoint behaviour on a match, `0b00` remaps instead
+    pub u8, replace, set_replace: 31, 30;
+    /// v1: Bits 28:2 of the compared address
+    pub comp, set_comp: 28, 2;
+    /// v1: Enable the comparator
+    pub enable, set_enable: 0;
+    /// v2: Bits 31:1 of the breakpoint address
+    pub bpaddr, set_bpaddr: 31, 1;
+    /// v2: Enable the breakpoint
+    pub be, set_be: 0;
+}
+
+impl FpComp {
+    /// Address of FP_COMP0
+    pub const ADDRESS: u32 = 0xE000_2008;
+    pub const NAME: &'static str = "FP_COMP";
+    /// Maximum number of comparators, 127 instruction and 15 literal ones
+    pub const COUNT: u32 = 142;
+
+    /// Comparator number of an FP_COMPn address
+    pub fn index(address: u32) -> Option<u8> {
+        let offset = address.checked_sub(Self::ADDRESS)?;
+        (offset % 4 == 0 && offset / 4 < Self::COUNT).then_some((offset / 4) as u8)
+    }
+
+    /// Bits reserved in the FPBv1 format, address bits in FPBv2
+    const V1_RESERVED: u32 = 1 << 29 | 1 << 1;
+
+    /// Guess the format when FP_CTRL wasn't read:
+    ///
+    /// - REPLACE set is an FPBv1 breakpoint, FPBv2 breakpoints are placed in
+    ///   the Code region, below 0x40000000
+    /// - an enabled comparator with REPLACE clear is taken as an FPBv2
+    ///   breakpoint rather than an FPBv1 remap, which debuggers don't use
+    /// - a disabled comparator is FPBv1 unless bits reserved there are set
+    pub fn guess_version(&self) -> FpbVersion {
+        if self.replace() != 0 {
+            FpbVersion::V1
+        } else if self.be() || self.0 & Self::V1_RESERVED != 0 {
+            FpbVersion::V2
+        } else {
+            FpbVersion::V1
+        }
+    }
+
+    /// The address of the enabled breakpoint, if any. For FPBv1 a comparator
+    /// breaking on both halfwords gives the word address
+    pub fn breakpoint(&self, version: FpbVersion) -> Option<u32> {
+        match version {
+            FpbVersion::V1 if self.enable() => match self.replace() {
+                0b01 | 0b11 => Some(self.comp() << 2),
+                0b10 => Some(self.comp() << 2 | 0b10),
+                _ => None,
+            },
+            FpbVersion::V2 if self.be() => Some(self.bpaddr() << 1),
+            _ => None,
+        }
+    }
+
+    /// The fields, formatted as the guessed version
+    fn fields(&self) -> String {
+        match self.guess_version() {
+            FpbVersion::V1 => format!(
+                "replace:{}, comp:{:08X}, enable:{}",
+                self.replace(),
+                self.comp() << 2,
+                self.enable() as u8
+            ),
+            FpbVersion::V2 => format!("bpaddr:{:08X}, be:{}", self.bpaddr() << 1, self.be() as u8),
+        }
+    }
+}
+
+impl fmt::Display for FpComp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({})", self.fields())
+    }
+}
+
+bitfield! {
+    /// DWT Control Register, DWT_CTRL (see armv7-M Architecture Reference Manual C1.8.7)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DwtCtrl(u32);
+    impl Debug;
+    /// Number of comparators implemented
+    pub u8, numcomp, _: 31, 28;
+    /// Trace sampling and exception tracing are not supported
+    pub notrcpkt, _: 27;
+    /// External match signals (CMPMATCH) are not supported
+    pub noexttrig, _: 26;
+    /// The cycle counter is not supported
+    pub nocyccnt, _: 25;
+    /// The profiling counters are not supported
+    pub noprfcnt, _: 24;
+    /// Armv8-M: Disable the cycle counter in Secure state
+    pub cycdiss, set_cycdiss: 23;
+    /// Enable POSTCNT underflow event counter packets
+    pub cycevtena, set_cycevtena: 22;
+    /// Enable folded instruction counter overflow event packets
+    pub foldevtena, set_foldevtena: 21;
+    /// Enable LSU counter overflow event packets
+    pub lsuevtena, set_lsuevtena: 20;
+    /// Enable sleep counter overflow event packets
+    pub sleepevtena, set_sleepevtena: 19;
+    /// Enable exception overhead counter overflow event packets
+    pub excevtena, set_excevtena: 18;
+    /// Enable CPI counter overflow event packets
+    pub cpievtena, set_cpievtena: 17;
+    /// Enable exception trace
+    pub exctrcena, set_exctrcena: 16;
+    /// Enable periodic PC sample packets
+    pub pcsamplena, set_pcsamplena: 12;
+    /// Position of the synchronisation packet counter tap on CYCCNT
+    pub u8, synctap, set_synctap: 11, 10;
+    /// Position of the POSTCNT tap on CYCCNT
+    pub cyctap, set_cyctap: 9;
+    /// Initial value of the POSTCNT counter
+    pub u8, postinit, set_postinit: 8, 5;
+    /// Reload value of the POSTCNT counter
+    pub u8, postpreset, set_postpreset: 4, 1;
+    /// Enable CYCCNT
+    pub cyccntena, set_cyccntena: 0;
+}
+
+impl DwtCtrl {
+    pub const ADDRESS: u32 = 0xE000_1000;
+    pub const NAME: &'static str = "DWT_CTRL";
+}
+
+impl fmt::Display for DwtCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(numcomp:{}, exctrcena:{}, pcsamplena:{}, cycevtena:{}, cyccntena:{})",
+            self.numcomp(),
+            self.exctrcena() as u8,
+            self.pcsamplena() as u8,
+            self.cycevtena() as u8,
+            self.cyccntena() as u8,
+        )
+    }
+}
+
+/// DWT Cycle Count Register, DWT_CYCCNT (see armv7-M Architecture Reference Manual C1.8.8)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DwtCyccnt(pub u32);
+
+impl DwtCyccnt {
+    pub const ADDRESS: u32 = 0xE000_1004;
+    pub const NAME: &'static str = "DWT_CYCCNT";
+}
+
+impl fmt::Display for DwtCyccnt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(cyccnt:{})", self.0)
+    }
+}
+
+bitfield! {
+    /// DWT CPI Count Register, DWT_CPICNT (see armv7-M Architecture Reference Manual C1.8.9)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DwtCpicnt(u32);
+    impl Debug;
+    /// Additional cycles of multi-cycle instructions and instruction fetch stalls
+    pub u8, cpicnt, set_cpicnt: 7, 0;
+}
+
+impl DwtCpicnt {
+    pub const ADDRESS: u32 = 0xE000_1008;
+    pub const NAME: &'static str = "DWT_CPICNT";
+}
+
+impl fmt::Display for DwtCpicnt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(cpicnt:{})", self.cpicnt())
+    }
+}
+
+bitfield! {
+    /// DWT Exception Overhead Count Register, DWT_EXCCNT (see armv7-M Architecture Reference Manual C1.8.10)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DwtExccnt(u32);
+    impl Debug;
+    /// Cycles spent in exception entry, exit and return
+    pub u8, exccnt, set_exccnt: 7, 0;
+}
+
+impl DwtExccnt {
+    pub const ADDRESS: u32 = 0xE000_100C;
+    pub const NAME: &'static str = "DWT_EXCCNT";
+}
+
+impl fmt::Display for DwtExccnt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(exccnt:{})", self.exccnt())
+    }
+}
+
+/// DWT Comparator Register, DWT_COMPn (see armv7-M Architecture Reference Manual C1.8.15)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DwtComp(pub u32);
+
+impl DwtComp {
+    /// Address of DWT_COMP0
+    pub const ADDRESS: u32 = 0xE000_1020;
+    pub const NAME: &'static str = "DWT_COMP";
+    /// Distance between the registers of consecutive comparators
+    pub const STRIDE: u32 = 0x10;
+    /// Maximum number of comparators
+    pub const COUNT: u32 = 16;
+
+    pub fn address(n: u8) -> u32 {
+        Self::ADDRESS + u32::from(n) * Self::STRIDE
+    }
+}
+
+bitfield! {
+    /// DWT Comparator Mask Register, DWT_MASKn, Armv7-M only (see armv7-M Architecture Reference Manual C1.8.16)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DwtMask(u32);
+    impl Debug;
+    /// Number of low address bits ignored by the comparison
+    pub u8, mask, set_mask: 4, 0;
+}
+
+impl DwtMask {
+    pub const NAME: &'static str = "DWT_MASK";
+
+    pub fn address(n: u8) -> u32 {
+        DwtComp::address(n) + 0x4
+    }
+}
+
+/// The M-profile architecture version, it defines the layout of the DWT and
+/// MPU registers that differ between Armv7-M and Armv8-M
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ArchVersion {
+    /// Armv6-M and Armv7-M
+    Armv7M,
+    Armv8M,
+}
+
+bitfield! {
+    /// DWT Function Register, DWT_FUNCTIONn, in both the Armv7-M (see
+    /// armv7-M Architecture Reference Manual C1.8.17) and Armv8-M (see
+    /// armv8-M Architecture Reference Manual) formats
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct DwtFunction(u32);
+    impl Debug;
+    /// v8: Identifies the capabilities of the comparator, never zero
+    pub u8, id, _: 31, 27;
+    /// The comparator matched since the register was last read
+    pub matched, _: 24;
+    /// v7: Second linked comparator for data value matching
+    pub u8, datavaddr1, set_datavaddr1: 19, 16;
+    /// v7: First linked comparator for data value matching
+    pub u8, datavaddr0, set_datavaddr0: 15, 12;
+    /// Size of the data value or data address match, `0b00` byte to `0b10` word
+    pub u8, datavsize, set_datavsize: 11, 10;
+    /// v7: Linked data value matching with a second comparator is supported
+    pub lnk1ena, _: 9;
+    /// v7: Data value comparison instead of address comparison
+    pub datavmatch, set_datavmatch: 8;
+    /// v7: Cycle counter comparison instead of PC comparison, comparator 0 only
+    pub cycmatch, set_cycmatch: 7;
+    /// v7: Emit data trace address offset packets instead of full addresses
+    pub emitrange, set_emitrange: 5;
+    /// v7: Action and type of access matched
+    pub u8, function, set_function: 3, 0;
+    /// v8: Action on a match
+    pub u8, action, set_action: 5, 4;
+    /// v8: Type of match
+    pub u8, match_type, set_match_type: 3, 0;
+}
+
+impl DwtFunction {
+    pub const NAME: &'static str = "DWT_FUNCTION";
+
+    pub fn address(n: u8) -> u32 {
+        DwtComp::address(n) + 0x8
+    }
+
+    /// Guess the architecture of a read value, Armv8-M comparators have a
+    /// non-zero ID. Written values leave ID zero
+    pub fn guess_arch(&self) -> ArchVersion {
+        if self.id() != 0 {
+            ArchVersion::Armv8M
+        } else {
+            ArchVersion::Armv7M
+        }
+    }
+
+    /// The configured function, `None` when the comparator is disabled or
+    /// configured with a reserved value
+    pub fn comparator_function(&self, arch: ArchVersion) -> Option<ComparatorFunction> {
+        match arch {
+            ArchVersion::Armv7M => self.armv7m_function(),
+            ArchVersion::Armv8M => self.armv8m_function(),
+        }
+    }
+
+    fn armv7m_function(&self) -> Option<ComparatorFunction> {
+        use ComparatorAction::*;
+        use ComparatorMatch::*;
+        use DataAccess::*;
+
+        let access = |function: u8| match function & 0x3 {
+            1 => Read,
+            2 => Write,
+            _ => ReadWrite,
+        };
+        let function = self.function();
+        let (action, on) = match function {
+            0 => return None,
+            1..=3 => (
+                Trace,
+                if function == 1 {
+                    InstructionAddress
+                } else {
+                    DataAddress(ReadWrite)
+                },
+            ),
+            4 => (Watchpoint, InstructionAddress),
+            5..=7 => (Watchpoint, DataAddress(access(function))),
+            8 => (Trigger, InstructionAddress),
+            9..=11 => (Trigger, DataAddress(access(function))),
+            12 | 14 => (Trace, DataAddress(Read)),
+            _ => (Trace, DataAddress(Write)),
+        };
+        let on = match on {
+            _ if self.cycmatch() => CycleCount,
+            DataAddress(access) if self.datavmatch() => DataValue(access),
+            on => on,
+        };
+        Some(ComparatorFunction { action, on })
+    }
+
+    fn armv8m_function(&self) -> Option<ComparatorFunction> {
+        use ComparatorMatch::*;
+        use DataAccess::*;
+
+        let action = match self.action() {
+            0 => ComparatorAction::Trigger,
+            1 => ComparatorAction::Watchpoint,
+            _ => ComparatorAction::Trace,
+        };
+        let on = match self.match_type() {
+            1 => CycleCount,
+            2 => InstructionAddress,
+            3 => InstructionAddressLimit,
+            4 | 12 => DataAddress(ReadWrite),
+            5 | 13 => DataAddress(Write),
+            6 | 14 => DataAddress(Read),
+            7 => DataAddressLimit,
+            8 => DataValue(ReadWrite),
+            9 => DataValue(Write),
+            10 => DataValue(Read),
+            11 => LinkedDataValue,
+            _ => return None,
+        };
+        Some(ComparatorFunction { action, on })
+    }
+
+    /// The fields, formatted as the guessed architecture
+    fn fields(&self) -> String {
+        match self.guess_arch() {
+            ArchVersion::Armv7M => format!(
+                "function:{}, cycmatch:{}, datavmatch:{}, datavsize:{}, matched:{}",
+                self.function(),
+                self.cycmatch() as u8,
+                self.datavmatch() as u8,
+                self.datavsize(),
+                self.matched() as u8
+            ),
+            ArchVersion::Armv8M => format!(
+                "id:{:02X}, action:{}, match:{}, datavsize:{}, matched:{}",
+                self.id(),
+                self.action(),
+                self.match_type(),
+                self.datavsize(),
+                self.matched() as u8
+            ),
+        }
+    }
+}
+
+/// What a DWT comparator does on a match
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ComparatorFunction {
+    pub action: ComparatorAction,
+    pub on: ComparatorMatch,
+}
+
+impl fmt::Display for ComparatorFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} on {}", self.action, self.on)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ComparatorAction {
+    /// Debug event, halting the core or a DebugMonitor exception
+    Watchpoint,
+    /// CMPMATCH trigger, e.g. for the ETM
+    Trigger,
+    /// Trace packets through the ITM
+    Trace,
+}
+
+impl fmt::Display for ComparatorAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComparatorAction::Watchpoint => "watchpoint",
+            ComparatorAction::Trigger => "trigger",
+            ComparatorAction::Trace => "trace",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ComparatorMatch {
+    CycleCount,
+    InstructionAddress,
+    /// Upper limit of an instruction address range, with the previous comparator
+    InstructionAddressLimit,
+    DataAddress(DataAccess),
+    /// Upper limit of a data address range, with the previous comparator
+    DataAddressLimit,
+    DataValue(DataAccess),
+    /// Data value with the address range of the previous comparator
+    LinkedDataValue,
+}
+
+impl fmt::Display for ComparatorMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparatorMatch::CycleCount => f.write_str("cycle count"),
+            ComparatorMatch::InstructionAddress => f.write_str("instruction address"),
+            ComparatorMatch::InstructionAddressLimit => f.write_str("instruction address limit"),
+            ComparatorMatch::DataAddress(access) => write!(f, "data {}", access),
+            ComparatorMatch::DataAddressLimit => f.write_str("data address limit"),
+            ComparatorMatch::DataValue(access) => write!(f, "data value {}", access),
+            ComparatorMatch::LinkedDataValue => f.write_str("linked data value"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum DataAccess {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl fmt::Display for DataAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DataAccess::Read => "read",
+            DataAccess::Write => "write",
+            DataAccess::ReadWrite => "read/write",
+        })
+    }
+}
+
+/// ITM Trace Enable Register, ITM_TERn (see armv7-M Architecture Reference Manual C1.7.4)
+///
+/// Each bit enables one stimulus port, ITM_TERn covers ports `32n` to `32n + 31`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ItmTer(pub u32);
+
+impl ItmTer {
+    /// Address of ITM_TER0
+    pub const ADDRESS: u32 = 0xE000_0E00;
+    pub const NAME: &'static str = "ITM_TER";
+    /// Number of registers for 256 stimulus ports
+    pub const COUNT: u32 = 8;
+
+    /// Register number of an ITM_TERn address
+    pub fn index(address: u32) -> Option<u8> {
+        let offset = address.checked_sub(Self::ADDRESS)?;
+        (offset % 4 == 0 && offset / 4 < Self::COUNT).then_some((offset / 4) as u8)
+    }
+}
+
+bitfield! {
+    /// ITM Trace Privilege Register, ITM_TPR (see armv7-M Architecture Reference Manual C1.7.5)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct ItmTpr(u32);
+    impl Debug;
+    /// Each bit restricts 8 stimulus ports to privileged accesses
+    pub privmask, set_privmask: 31, 0;
+}
+
+impl ItmTpr {
+    pub const ADDRESS: u32 = 0xE000_0E40;
+    pub const NAME: &'static str = "ITM_TPR";
+}
+
+impl fmt::Display for ItmTpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(privmask:{:08X})", self.privmask())
+    }
+}
+
+bitfield! {
+    /// ITM Trace Control Register, ITM_TCR (see armv7-M Architecture Reference Manual C1.7.6)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct ItmTcr(u32);
+    impl Debug;
+    /// The ITM is processing events
+    pub busy, _: 23;
+    /// Identifier of the ITM on the trace bus
+    pub u8, tracebusid, set_tracebusid: 22, 16;
+    /// Global timestamp frequency
+    pub u8, gtsfreq, set_gtsfreq: 11, 10;
+    /// Local timestamp prescaler, divides the reference clock by 1, 4, 16 or 64
+    pub u8, tsprescale, set_tsprescale: 9, 8;
+    /// Use the SWO clock as the timestamp counter reference
+    pub swoena, set_swoena: 4;
+    /// Forward DWT packets to the ITM
+    pub txena, set_txena: 3;
+    /// Enable synchronisation packets
+    pub syncena, set_syncena: 2;
+    /// Enable local timestamps
+    pub tsena, set_tsena: 1;
+    /// Enable the ITM
+    pub itmena, set_itmena: 0;
+}
+
+impl ItmTcr {
+    pub const ADDRESS: u32 = 0xE000_0E80;
+    pub const NAME: &'static str = "ITM_TCR";
+}
+
+impl fmt::Display for ItmTcr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(tracebusid:{}, tsprescale:{}, swoena:{}, txena:{}, syncena:{}, tsena:{}, itmena:{})",
+            self.tracebusid(),
+            self.tsprescale(),
+            self.swoena() as u8,
+            self.txena() as u8,
+            self.syncena() as u8,
+            self.tsena() as u8,
+            self.itmena() as u8,
+        )
+    }
+}
+
+/// ITM Lock Access Register, ITM_LAR (see CoreSight Architecture Specification)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ItmLar(pub u32);
+
+impl ItmLar {
+    pub const ADDRESS: u32 = 0xE000_0FB0;
+    pub const NAME: &'static str = "ITM_LAR";
+    /// Writing this key unlocks the registers for writes
+    pub const KEY: u32 = 0xC5AC_CE55;
+
+    pub fn unlocks(&self) -> bool {
+        self.0 == Self::KEY
+    }
+}
+
+impl fmt::Display for ItmLar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unlocks() {
+            f.write_str("(unlock)")
+        } else {
+            f.write_str("(lock)")
+        }
+    }
+}
+
+bitfield! {
+    /// TPIU Asynchronous Clock Prescaler Register, TPIU_ACPR (see armv7-M Architecture Reference Manual C1.10.4)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct TpiuAcpr(u32);
+    impl Debug;
+    /// The SWO clock is the reference clock divided by `SWOSCALER + 1`
+    pub u16, swoscaler, set_swoscaler: 15, 0;
+}
+
+impl TpiuAcpr {
+    pub const ADDRESS: u32 = 0xE004_0010;
+    pub const NAME: &'static str = "TPIU_ACPR";
+
+    /// Reference clock divisor
+    pub fn prescaler(&self) -> u32 {
+        u32::from(self.swoscaler()) + 1
+    }
+}
+
+impl fmt::Display for TpiuAcpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(swoscaler:{})", self.swoscaler())
+    }
+}
+
+bitfield! {
+    /// TPIU Selected Pin Protocol Register, TPIU_SPPR (see armv7-M Architecture Reference Manual C1.10.5)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct TpiuSppr(u32);
+    impl Debug;
+    /// The protocol for trace output, see [`PinProtocol`]
+    pub u8, txmode, set_txmode: 1, 0;
+}
+
+impl TpiuSppr {
+    pub const ADDRESS: u32 = 0xE004_00F0;
+    pub const NAME: &'static str = "TPIU_SPPR";
+
+    /// `None` for the reserved value
+    pub fn protocol(&self) -> Option<PinProtocol> {
+        match self.txmode() {
+            0 => Some(PinProtocol::Parallel),
+            1 => Some(PinProtocol::Manchester),
+            2 => Some(PinProtocol::Nrz),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TpiuSppr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protocol() {
+            Some(protocol) => write!(f, "(txmode:{})", protocol),
+            None => write!(f, "(txmode:{})", self.txmode()),
+        }
+    }
+}
+
+/// Trace output protocol
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum PinProtocol {
+    /// Synchronous trace port
+    Parallel,
+    /// SWO with Manchester encoding
+    Manchester,
+    /// SWO with NRZ (UART) encoding
+    Nrz,
+}
+
+impl fmt::Display for PinProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PinProtocol::Parallel => "parallel",
+            PinProtocol::Manchester => "Manchester",
+            PinProtocol::Nrz => "NRZ",
+        })
+    }
+}
+
+bitfield! {
+    /// TPIU Formatter and Flush Control Register, TPIU_FFCR (see armv7-M Architecture Reference Manual C1.10.7)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct TpiuFfcr(u32);
+    impl Debug;
+    /// Indicate a trigger on TRIGIN
+    pub trigin, set_trigin: 8;
+    /// Continuous formatting, wraps ITM and ETM packets in formatter frames
+    pub enfcont, set_enfcont: 1;
+}
+
+impl TpiuFfcr {
+    pub const ADDRESS: u32 = 0xE004_0304;
+    pub const NAME: &'static str = "TPIU_FFCR";
+}
+
+impl fmt::Display for TpiuFfcr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(trigin:{}, enfcont:{})",
+            self.trigin() as u8,
+            self.enfcont() as u8
+        )
+    }
+}
+
+/// Write the names of the flags set, as `(a, b)`
+fn write_flags(f: &mut fmt::Formatter<'_>, flags: &[(&str, bool)]) -> fmt::Result {
+    let mut set = flags.iter().filter(|(_, set)| *set).map(|(name, _)| name);
+    match set.next() {
+        Some(first) => {
+            write!(f, "({}", first)?;
+            for name in set {
+                write!(f, ", {}", name)?;
+            }
+            f.write_str(")")
+        }
+        None => f.write_str("(none)"),
+    }
+}
+
+bitfield! {
+    /// CPUID Base Register, CPUID (see armv7-M Architecture Reference Manual B4.1.2)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Cpuid(u32);
+    impl Debug;
+    /// Implementer code, `0x41` for Arm
+    pub u8, implementer, _: 31, 24;
+    /// The `r` of the `rNpM` product revision
+    pub u8, variant, _: 23, 20;
+    /// `0xF` for Armv7-M, `0xC` for Armv6-M and Armv8-M Baseline
+    pub u8, architecture, _: 19, 16;
+    pub u16, partno, _: 15, 4;
+    /// The `p` of the `rNpM` product revision
+    pub u8, revision, _: 3, 0;
+}
+
+impl Cpuid {
+    pub const ADDRESS: u32 = 0xE000_ED00;
+    pub const NAME: &'static str = "CPUID";
+    pub const IMPLEMENTER_ARM: u8 = 0x41;
+
+    /// Name of an Arm Cortex-M processor
+    pub fn part_name(&self) -> Option<&'static str> {
+        if self.implementer() != Self::IMPLEMENTER_ARM {
+            return None;
+        }
+        Some(match self.partno() {
+            0xC20 => "Cortex-M0",
+            0xC60 => "Cortex-M0+",
+            0xC21 => "Cortex-M1",
+            0xC23 => "Cortex-M3",
+            0xC24 => "Cortex-M4",
+            0xC27 => "Cortex-M7",
+            0xD20 => "Cortex-M23",
+            0xD21 => "Cortex-M33",
+            0xD31 => "Cortex-M35P",
+            0xD24 => "Cortex-M52",
+            0xD22 => "Cortex-M55",
+            0xD23 => "Cortex-M85",
+            _ => return None,
+        })
+    }
+}
+
+impl Cpuid {
+    /// The architecture of an Arm Cortex-M processor
+    pub fn arch_version(&self) -> Option<ArchVersion> {
+        if self.implementer() != Self::IMPLEMENTER_ARM {
+            return None;
+        }
+        match self.partno() {
+            0xC20 | 0xC60 | 0xC21 | 0xC23 | 0xC24 | 0xC27 => Some(ArchVersion::Armv7M),
+            0xD20..=0xD24 | 0xD31 => Some(ArchVersion::Armv8M),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Cpuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.part_name() {
+            Some(name) => write!(f, "(Arm {}", name)?,
+            None => write!(
+                f,
+                "(implementer:{:02X}, partno:{:03X}",
+                self.implementer(),
+                self.partno()
+            )?,
+        }
+        write!(f, " r{}p{})", self.variant(), self.revision())
+    }
+}
+
+bitfield! {
+    /// Vector Table Offset Register, VTOR (see armv7-M Architecture Reference Manual B3.2.5)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Vtor(u32);
+    impl Debug;
+    /// Bits 31:7 of the vector table address
+    pub tbloff, set_tbloff: 31, 7;
+}
+
+impl Vtor {
+    pub const ADDRESS: u32 = 0xE000_ED08;
+    pub const NAME: &'static str = "VTOR";
+
+    /// Address of the vector table
+    pub fn address(&self) -> u32 {
+        self.tbloff() << 7
+    }
+}
+
+impl fmt::Display for Vtor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(tbloff:{:08X})", self.address())
+    }
+}
+
+bitfield! {
+    /// System Handler Control and State Register, SHCSR (see armv7-M Architecture Reference Manual B3.2.13)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Shcsr(u32);
+    impl Debug;
+    /// Armv8-M: HardFault is pending
+    pub hardfaultpended, set_hardfaultpended: 21;
+    /// Armv8-M: SecureFault is pending
+    pub securefaultpended, set_securefaultpended: 20;
+    /// Armv8-M: Enable SecureFault
+    pub securefaultena, set_securefaultena: 19;
+    /// Enable UsageFault
+    pub usgfaultena, set_usgfaultena: 18;
+    /// Enable BusFault
+    pub busfaultena, set_busfaultena: 17;
+    /// Enable MemManage fault
+    pub memfaultena, set_memfaultena: 16;
+    pub svcallpended, set_svcallpended: 15;
+    pub busfaultpended, set_busfaultpended: 14;
+    pub memfaultpended, set_memfaultpended: 13;
+    pub usgfaultpended, set_usgfaultpended: 12;
+    pub systickact, set_systickact: 11;
+    pub pendsvact, set_pendsvact: 10;
+    pub monitoract, set_monitoract: 8;
+    pub svcallact, set_svcallact: 7;
+    /// Armv8-M: NMI is active
+    pub nmiact, set_nmiact: 5;
+    /// Armv8-M: SecureFault is active
+    pub securefaultact, set_securefaultact: 4;
+    pub usgfaultact, set_usgfaultact: 3;
+    /// Armv8-M: HardFault is active
+    pub hardfaultact, set_hardfaultact: 2;
+    pub busfaultact, set_busfaultact: 1;
+    pub memfaultact, set_memfaultact: 0;
+}
+
+impl Shcsr {
+    pub const ADDRESS: u32 = 0xE000_ED24;
+    pub const NAME: &'static str = "SHCSR";
+}
+
+impl fmt::Display for Shcsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                ("hardfaultpended", self.hardfaultpended()),
+                ("securefaultpended", self.securefaultpended()),
+                ("securefaultena", self.securefaultena()),
+                ("usgfaultena", self.usgfaultena()),
+                ("busfaultena", self.busfaultena()),
+                ("memfaultena", self.memfaultena()),
+                ("svcallpended", self.svcallpended()),
+                ("busfaultpended", self.busfaultpended()),
+                ("memfaultpended", self.memfaultpended()),
+                ("usgfaultpended", self.usgfaultpended()),
+                ("systickact", self.systickact()),
+                ("pendsvact", self.pendsvact()),
+                ("monitoract", self.monitoract()),
+                ("svcallact", self.svcallact()),
+                ("nmiact", self.nmiact()),
+                ("securefaultact", self.securefaultact()),
+                ("usgfaultact", self.usgfaultact()),
+                ("hardfaultact", self.hardfaultact()),
+                ("busfaultact", self.busfaultact()),
+                ("memfaultact", self.memfaultact()),
+            ],
+        )
+    }
+}
+
+bitfield! {
+    /// Configurable Fault Status Register, CFSR, combining MMFSR, BFSR and
+    /// UFSR (see armv7-M Architecture Reference Manual B3.2.15)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Cfsr(u32);
+    impl Debug;
+    /// UFSR: Integer division by zero, when trapped
+    pub divbyzero, _: 25;
+    /// UFSR: Unaligned access, when trapped
+    pub unaligned, _: 24;
+    /// UFSR, Armv8-M: Stack overflow on a stack limit check
+    pub stkof, _: 20;
+    /// UFSR: Coprocessor access while disabled or absent
+    pub nocp, _: 19;
+    /// UFSR: Invalid EXC_RETURN on exception return
+    pub invpc, _: 18;
+    /// UFSR: Invalid EPSR state, e.g. executing with the Thumb bit clear
+    pub invstate, _: 17;
+    /// UFSR: Undefined instruction
+    pub undefinstr, _: 16;
+    /// BFSR: BFAR holds the faulting address
+    pub bfarvalid, _: 15;
+    /// BFSR: Bus fault during floating-point lazy state preservation
+    pub lsperr, _: 13;
+    /// BFSR: Bus fault on exception entry stacking
+    pub stkerr, _: 12;
+    /// BFSR: Bus fault on exception return unstacking
+    pub unstkerr, _: 11;
+    /// BFSR: Imprecise data bus error
+    pub impreciserr, _: 10;
+    /// BFSR: Precise data bus error, at BFAR
+    pub preciserr, _: 9;
+    /// BFSR: Instruction bus error
+    pub ibuserr, _: 8;
+    /// MMFSR: MMFAR holds the faulting address
+    pub mmarvalid, _: 7;
+    /// MMFSR: MemManage fault during floating-point lazy state preservation
+    pub mlsperr, _: 5;
+    /// MMFSR: MemManage fault on exception entry stacking
+    pub mstkerr, _: 4;
+    /// MMFSR: MemManage fault on exception return unstacking
+    pub munstkerr, _: 3;
+    /// MMFSR: Data access violation, at MMFAR
+    pub daccviol, _: 1;
+    /// MMFSR: Instruction access violation
+    pub iaccviol, _: 0;
+}
+
+impl Cfsr {
+    pub const ADDRESS: u32 = 0xE000_ED28;
+    pub const NAME: &'static str = "CFSR";
+}
+
+impl fmt::Display for Cfsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                ("divbyzero", self.divbyzero()),
+                ("unaligned", self.unaligned()),
+                ("stkof", self.stkof()),
+                ("nocp", self.nocp()),
+                ("invpc", self.invpc()),
+                ("invstate", self.invstate()),
+                ("undefinstr", self.undefinstr()),
+                ("bfarvalid", self.bfarvalid()),
+                ("lsperr", self.lsperr()),
+                ("stkerr", self.stkerr()),
+                ("unstkerr", self.unstkerr()),
+                ("impreciserr", self.impreciserr()),
+                ("preciserr", self.preciserr()),
+                ("ibuserr", self.ibuserr()),
+                ("mmarvalid", self.mmarvalid()),
+                ("mlsperr", self.mlsperr()),
+                ("mstkerr", self.mstkerr()),
+                ("munstkerr", self.munstkerr()),
+                ("daccviol", self.daccviol()),
+                ("iaccviol", self.iaccviol()),
+            ],
+        )
+    }
+}
+
+bitfield! {
+    /// HardFault Status Register, HFSR (see armv7-M Architecture Reference Manual B3.2.16)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Hfsr(u32);
+    impl Debug;
+    /// A debug event occurred with halting debug disabled
+    pub debugevt, _: 31;
+    /// A configurable fault was escalated to HardFault
+    pub forced, _: 30;
+    /// Bus fault on a vector table read during exception processing
+    pub vecttbl, _: 1;
+}
+
+impl Hfsr {
+    pub const ADDRESS: u32 = 0xE000_ED2C;
+    pub const NAME: &'static str = "HFSR";
+}
+
+impl fmt::Display for Hfsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                ("debugevt", self.debugevt()),
+                ("forced", self.forced()),
+                ("vecttbl", self.vecttbl()),
+            ],
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Fault Status Register, DFSR (see armv7-M Architecture Reference Manual C1.6.1)
+    ///
+    /// Records why the core entered Debug state, bits are cleared by writing `1`.
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dfsr(u32);
+    impl Debug;
+    /// Armv8.1-M: PMU counter overflow
+    pub pmu, _: 5;
+    /// External debug request (EDBGRQ)
+    pub external, _: 4;
+    /// Vector catch, see DEMCR
+    pub vcatch, _: 3;
+    /// DWT watchpoint or trigger
+    pub dwttrap, _: 2;
+    /// BKPT instruction or FPB breakpoint
+    pub bkpt, _: 1;
+    /// Halt request (C_HALT) or single step
+    pub halted, _: 0;
+}
+
+impl Dfsr {
+    pub const ADDRESS: u32 = 0xE000_ED30;
+    pub const NAME: &'static str = "DFSR";
+}
+
+impl fmt::Display for Dfsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_flags(
+            f,
+            &[
+                ("pmu", self.pmu()),
+                ("external", self.external()),
+                ("vcatch", self.vcatch()),
+                ("dwttrap", self.dwttrap()),
+                ("bkpt", self.bkpt()),
+                ("halted", self.halted()),
+            ],
+        )
+    }
+}
+
+/// MemManage Fault Address Register, MMFAR (see armv7-M Architecture Reference Manual B3.2.17)
+///
+/// Valid when [`Cfsr::mmarvalid`] is set.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Mmfar(pub u32);
+
+impl Mmfar {
+    pub const ADDRESS: u32 = 0xE000_ED34;
+    pub const NAME: &'static str = "MMFAR";
+}
+
+impl fmt::Display for Mmfar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(address:{:08X})", self.0)
+    }
+}
+
+/// BusFault Address Register, BFAR (see armv7-M Architecture Reference Manual B3.2.18)
+///
+/// Valid when [`Cfsr::bfarvalid`] is set.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Bfar(pub u32);
+
+impl Bfar {
+    pub const ADDRESS: u32 = 0xE000_ED38;
+    pub const NAME: &'static str = "BFAR";
+}
+
+impl fmt::Display for Bfar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(address:{:08X})", self.0)
+    }
+}
+
+bitfield! {
+    /// MPU Type Register, MPU_TYPE (see armv7-M Architecture Reference Manual B3.5.5)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct MpuType(u32);
+    impl Debug;
+    /// Number of regions supported, `0` without an MPU
+    pub u8, dregion, _: 15, 8;
+    /// Separate instruction and data regions, always `0`
+    pub separate, _: 0;
+}
+
+impl MpuType {
+    pub const ADDRESS: u32 = 0xE000_ED90;
+    pub const NAME: &'static str = "MPU_TYPE";
+}
+
+impl fmt::Display for MpuType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(dregion:{})", self.dregion())
+    }
+}
+
+bitfield! {
+    /// MPU Control Register, MPU_CTRL (see armv7-M Architecture Reference Manual B3.5.6)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct MpuCtrl(u32);
+    impl Debug;
+    /// Use the default memory map as a background region for privileged accesses
+    pub privdefena, set_privdefena: 2;
+    /// Keep the MPU enabled during HardFault, NMI and FAULTMASK escalated handlers
+    pub hfnmiena, set_hfnmiena: 1;
+    /// Enable the MPU
+    pub enable, set_enable: 0;
+}
+
+impl MpuCtrl {
+    pub const ADDRESS: u32 = 0xE000_ED94;
+    pub const NAME: &'static str = "MPU_CTRL";
+}
+
+impl fmt::Display for MpuCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(privdefena:{}, hfnmiena:{}, enable:{})",
+            self.privdefena() as u8,
+            self.hfnmiena() as u8,
+            self.enable() as u8
+        )
+    }
+}
+
+bitfield! {
+    /// MPU Region Number Register, MPU_RNR (see armv7-M Architecture Reference Manual B3.5.7)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct MpuRnr(u32);
+    impl Debug;
+    /// The region accessed by MPU_RBAR and MPU_RASR/MPU_RLAR
+    pub u8, region, set_region: 7, 0;
+}
+
+impl MpuRnr {
+    pub const ADDRESS: u32 = 0xE000_ED98;
+    pub const NAME: &'static str = "MPU_RNR";
+}
+
+bitfield! {
+    /// MPU Region Base Address Register, MPU_RBAR, in both the Armv7-M (see
+    /// armv7-M Architecture Reference Manual B3.5.8) and Armv8-M (see armv8-M
+    /// Architecture Reference Manual) formats
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct MpuRbar(u32);
+    impl Debug;
+    /// Bits 31:5 of the region base address, v7 regions are aligned to their size
+    pub addr, set_addr: 31, 5;
+    /// v7: Write REGION to MPU_RNR before updating the base address
+    pub valid, set_valid: 4;
+    /// v7: The region number when VALID is set
+    pub u8, region, set_region: 3, 0;
+    /// v8: Shareability
+    pub u8, sh, set_sh: 4, 3;
+    /// v8: Access permissions, read-only and unprivileged access bits
+    pub u8, ap, set_ap: 2, 1;
+    /// v8: Execute never
+    pub xn, set_xn: 0;
+}
+
+impl MpuRbar {
+    /// Address of MPU_RBAR, MPU_RBAR_An follow every 8 bytes
+    pub const ADDRESS: u32 = 0xE000_ED9C;
+    pub const NAME: &'static str = "MPU_RBAR";
+    /// Number of aliases, including MPU_RBAR itself
+    pub const ALIASES: u32 = 4;
+
+    /// Alias number of an MPU_RBAR or MPU_RBAR_An address
+    pub fn alias(address: u32) -> Option<u8> {
+        let offset = address.checked_sub(Self::ADDRESS)?;
+        (offset % 8 == 0 && offset / 8 < Self::ALIASES).then_some((offset / 8) as u8)
+    }
+
+    /// Region base address
+    pub fn address(&self) -> u32 {
+        self.addr() << 5
+    }
+
+    /// The fields in the Armv7-M format, the value alone can't tell the
+    /// architecture
+    fn fields(&self) -> String {
+        format!(
+            "addr:{:08X}, valid:{}, region:{}",
+            self.address(),
+            self.valid() as u8,
+            self.region()
+        )
+    }
+}
+
+bitfield! {
+    /// MPU Region Attribute and Size Register, MPU_RASR (see armv7-M
+    /// Architecture Reference Manual B3.5.9), at the address of the Armv8-M
+    /// MPU Region Limit Address Register, MPU_RLAR (see armv8-M Architecture
+    /// Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct MpuRasr(u32);
+    impl Debug;
+    /// v7: Execute never
+    pub xn, set_xn: 28;
+    /// v7: Access permissions
+    pub u8, ap, set_ap: 26, 24;
+    /// v7: Memory type, with C and B
+    pub u8, tex, set_tex: 21, 19;
+    /// v7: Shareable
+    pub s, set_s: 18;
+    /// v7: Cacheable
+    pub c, set_c: 17;
+    /// v7: Bufferable
+    pub b, set_b: 16;
+    /// v7: Each bit disables one eighth of the region
+    pub u8, srd, set_srd: 15, 8;
+    /// v7: The region size is `2^(SIZE + 1)` bytes
+    pub u8, size, set_size: 5, 1;
+    /// v7: Enable the region
+    pub enable, set_enable: 0;
+    /// v8: Bits 31:5 of the region limit address, the last 32-byte block
+    pub limit, set_limit: 31, 5;
+    /// v8.1: Privileged execute never
+    pub pxn, set_pxn: 4;
+    /// v8: Index of the MPU_MAIRn attributes
+    pub u8, attrindx, set_attrindx: 3, 1;
+    /// v8: Enable the region
+    pub en, set_en: 0;
+}
+
+impl MpuRasr {
+    /// Address of MPU_RASR, MPU_RASR_An follow every 8 bytes
+    pub const ADDRESS: u32 = 0xE000_EDA0;
+    pub const NAME: &'static str = "MPU_RASR";
+    pub const NAME_V8: &'static str = "MPU_RLAR";
+
+    /// Alias number of an MPU_RASR or MPU_RASR_An address
+    pub fn alias(address: u32) -> Option<u8> {
+        let offset = address.checked_sub(Self::ADDRESS)?;
+        (offset % 8 == 0 && offset / 8 < MpuRbar::ALIASES).then_some((offset / 8) as u8)
+    }
+
+    /// Guess the architecture from the bits reserved in MPU_RASR, MPU_RLAR
+    /// limits usually lie above 0x20000000
+    pub fn guess_arch(&self) -> ArchVersion {
+        if self.0 & 0xE8C0_00C0 != 0 {
+            ArchVersion::Armv8M
+        } else {
+            ArchVersion::Armv7M
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.guess_arch() {
+            ArchVersion::Armv7M => Self::NAME,
+            ArchVersion::Armv8M => Self::NAME_V8,
+        }
+    }
+
+    /// v7: Region size in bytes
+    pub fn size_bytes(&self) -> u64 {
+        1 << (u32::from(self.size()) + 1)
+    }
+
+    /// v8: Address of the last byte of the region
+    pub fn limit_address(&self) -> u32 {
+        self.limit() << 5 | 0x1F
+    }
+
+    /// The fields, formatted as the guessed architecture
+    fn fields(&self) -> String {
+        match self.guess_arch() {
+            ArchVersion::Armv7M => format!(
+                "size:{}, srd:{:02X}, xn:{}, ap:{}, tex:{}, s:{}, c:{}, b:{}, enable:{}",
+                format_size(self.size_bytes()),
+                self.srd(),
+                self.xn() as u8,
+                self.ap(),
+                self.tex(),
+                self.s() as u8,
+                self.c() as u8,
+                self.b() as u8,
+                self.enable() as u8
+            ),
+            ArchVersion::Armv8M => format!(
+                "limit:{:08X}, pxn:{}, attrindx:{}, en:{}",
+                self.limit_address(),
+                self.pxn() as u8,
+                self.attrindx(),
+                self.en() as u8
+            ),
+        }
+    }
+}
+
+/// A size in bytes with a binary unit, e.g. `64KiB`
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 && b % (1 << 30) == 0 => format!("{}GiB", b >> 30),
+        b if b >= 1 << 20 && b % (1 << 20) == 0 => format!("{}MiB", b >> 20),
+        b if b >= 1 << 10 && b % (1 << 10) == 0 => format!("{}KiB", b >> 10),
+        b => format!("{}B", b),
+    }
+}
+
+/// MPU Memory Attribute Indirection Register, MPU_MAIRn, Armv8-M only (see armv8-M Architecture Reference Manual)
+///
+/// Holds four 8-bit memory attributes, selected by MPU_RLAR.ATTRINDX.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MpuMair(pub u32);
+
+impl MpuMair {
+    /// Address of MPU_MAIR0
+    pub const ADDRESS: u32 = 0xE000_EDC0;
+    pub const NAME: &'static str = "MPU_MAIR";
+
+    /// Register number of an MPU_MAIRn address
+    pub fn index(address: u32) -> Option<u8> {
+        match address {
+            Self::ADDRESS => Some(0),
+            0xE000_EDC4 => Some(1),
+            _ => None,
+        }
+    }
+}
+
+bitfield! {
+    /// SAU Control Register, SAU_CTRL (see armv8-M Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct SauCtrl(u32);
+    impl Debug;
+    /// With the SAU disabled, memory is Non-secure instead of Secure
+    pub allns, set_allns: 1;
+    /// Enable the SAU
+    pub enable, set_enable: 0;
+}
+
+impl SauCtrl {
+    pub const ADDRESS: u32 = 0xE000_EDD0;
+    pub const NAME: &'static str = "SAU_CTRL";
+}
+
+impl fmt::Display for SauCtrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(allns:{}, enable:{})",
+            self.allns() as u8,
+            self.enable() as u8
+        )
+    }
+}
+
+bitfield! {
+    /// SAU Type Register, SAU_TYPE (see armv8-M Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct SauType(u32);
+    impl Debug;
+    /// Number of regions supported
+    pub u8, sregion, _: 7, 0;
+}
+
+impl SauType {
+    pub const ADDRESS: u32 = 0xE000_EDD4;
+    pub const NAME: &'static str = "SAU_TYPE";
+}
+
+bitfield! {
+    /// SAU Region Number Register, SAU_RNR (see armv8-M Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct SauRnr(u32);
+    impl Debug;
+    /// The region accessed by SAU_RBAR and SAU_RLAR
+    pub u8, region, set_region: 7, 0;
+}
+
+impl SauRnr {
+    pub const ADDRESS: u32 = 0xE000_EDD8;
+    pub const NAME: &'static str = "SAU_RNR";
+}
+
+bitfield! {
+    /// SAU Region Base Address Register, SAU_RBAR (see armv8-M Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct SauRbar(u32);
+    impl Debug;
+    /// Bits 31:5 of the region base address
+    pub baddr, set_baddr: 31, 5;
+}
+
+impl SauRbar {
+    pub const ADDRESS: u32 = 0xE000_EDDC;
+    pub const NAME: &'static str = "SAU_RBAR";
+
+    pub fn address(&self) -> u32 {
+        self.baddr() << 5
+    }
+}
+
+bitfield! {
+    /// SAU Region Limit Address Register, SAU_RLAR (see armv8-M Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct SauRlar(u32);
+    impl Debug;
+    /// Bits 31:5 of the region limit address, the last 32-byte block
+    pub laddr, set_laddr: 31, 5;
+    /// Non-secure callable instead of Non-secure
+    pub nsc, set_nsc: 1;
+    /// Enable the region
+    pub enable, set_enable: 0;
+}
+
+impl SauRlar {
+    pub const ADDRESS: u32 = 0xE000_EDE0;
+    pub const NAME: &'static str = "SAU_RLAR";
+
+    /// Address of the last byte of the region
+    pub fn limit_address(&self) -> u32 {
+        self.laddr() << 5 | 0x1F
+    }
+}
+
+impl fmt::Display for SauRlar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(laddr:{:08X}, nsc:{}, enable:{})",
+            self.limit_address(),
+            self.nsc() as u8,
+            self.enable() as u8
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Security Control and Status Register, DSCSR, Armv8-M with the Security Extension (see armv8-M
+    /// Architecture Reference Manual)
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dscsr(u32);
+    impl Debug;
+    /// Writes to CDS are ignored unless CDSKEY is written as `0`
+    pub cdskey, set_cdskey: 17;
+    /// Current domain Secure, the Security state the core is in or returns to
+    pub cds, set_cds: 16;
+    /// With SBRSELEN set, debugger accesses are Secure (`1`) or Non-secure (`0`)
+    pub sbrsel, set_sbrsel: 1;
+    /// Select the debugger access security with SBRSEL instead of the current
+    /// Security state
+    pub sbrselen, set_sbrselen: 0;
+}
+
+impl Dscsr {
+    pub const ADDRESS: u32 = 0xE000_EE08;
+    pub const NAME: &'static str = "DSCSR";
+}
+
+impl fmt::Display for Dscsr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(cdskey:{}, cds:{}, sbrsel:{}, sbrselen:{})",
+            self.cdskey() as u8,
+            self.cds() as u8,
+            self.sbrsel() as u8,
+            self.sbrselen() as u8
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Authentication Control Register, DAUTHCTRL, Armv8-M (see armv8-M Architecture Reference Manual)
+    ///
+    /// Lets Secure software override the external authentication signals.
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dauthctrl(u32);
+    impl Debug;
+    /// Internal Secure non-invasive debug enable, used with SPNIDENSEL
+    pub intspniden, set_intspniden: 3;
+    /// Select INTSPNIDEN instead of the SPNIDEN signal
+    pub spnidensel, set_spnidensel: 2;
+    /// Internal Secure invasive debug enable, used with SPIDENSEL
+    pub intspiden, set_intspiden: 1;
+    /// Select INTSPIDEN instead of the SPIDEN signal
+    pub spidensel, set_spidensel: 0;
+}
+
+impl Dauthctrl {
+    pub const ADDRESS: u32 = 0xE000_EE04;
+    pub const NAME: &'static str = "DAUTHCTRL";
+}
+
+impl fmt::Display for Dauthctrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(intspniden:{}, spnidensel:{}, intspiden:{}, spidensel:{})",
+            self.intspniden() as u8,
+            self.spnidensel() as u8,
+            self.intspiden() as u8,
+            self.spidensel() as u8
+        )
+    }
+}
+
+bitfield! {
+    /// Debug Authentication Status Register, DAUTHSTATUS (see armv8-M Architecture Reference Manual)
+    ///
+    /// Each field is `0b00` when the Security state isn't implemented, `0b10`
+    /// when the debug is disabled and `0b11` when enabled.
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct Dauthstatus(u32);
+    impl Debug;
+    /// Secure non-invasive debug
+    pub u8, snid, _: 7, 6;
+    /// Secure invasive debug
+    pub u8, sid, _: 5, 4;
+    /// Non-secure non-invasive debug
+    pub u8, nsnid, _: 3, 2;
+    /// Non-secure invasive debug
+    pub u8, nsid, _: 1, 0;
+}
+
+impl Dauthstatus {
+    pub const ADDRESS: u32 = 0xE000_EFB8;
+    pub const NAME: &'static str = "DAUTHSTATUS";
+
+    /// Whether the debugger can halt and debug the core in Secure state
+    pub fn secure_debug(&self) -> bool {
+        self.sid() == 0b11
+    }
+}
+
+impl fmt::Display for Dauthstatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = |field| match field {
+            0b00 => "n/a",
+            0b10 => "disabled",
+            0b11 => "enabled",
+            _ => "?",
+        };
+        write!(
+            f,
+            "(sid:{}, snid:{}, nsid:{}, nsnid:{})",
+            state(self.sid()),
+            state(self.snid()),
+            state(self.nsid()),
+            state(self.nsnid())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(address: u32, value: u32) -> String {
+        let register = ArmRegister::decode(address, value, Direction::Read).unwrap();
+        format!("{} {}", register.name(), register)
+    }
+
+    fn decode_write(address: u32, value: u32) -> String {
+        let register = ArmRegister::decode(address, value, Direction::Write).unwrap();
+        format!("{} {}", register.name(), register)
+    }
+
+    #[test]
+    fn scb_registers() {
+        assert_eq!(
+            decode(0xE000_ED00, 0x411F_C272),
+            "CPUID (Arm Cortex-M7 r1p2)"
+        );
+        assert_eq!(
+            decode(0xE000_ED00, 0x410C_C601),
+            "CPUID (Arm Cortex-M0+ r0p1)"
+        );
+        assert_eq!(
+            decode(0xE000_ED00, 0x5100_1234),
+            "CPUID (implementer:51, partno:123 r0p4)"
+        );
+        assert_eq!(decode(0xE000_ED08, 0x0040_0000), "VTOR (tbloff:00400000)");
+        assert_eq!(
+            decode(0xE000_ED28, 0x0000_8200),
+            "CFSR (bfarvalid, preciserr)"
+        );
+        assert_eq!(decode(0xE000_ED28, 0), "CFSR (none)");
+        assert_eq!(decode(0xE000_ED2C, 0x4000_0000), "HFSR (forced)");
+        assert_eq!(decode(0xE000_ED30, 0x0000_0003), "DFSR (bkpt, halted)");
+        assert_eq!(
+            decode(0xE000_ED24, 0x0007_0000),
+            "SHCSR (usgfaultena, busfaultena, memfaultena)"
+        );
+        assert_eq!(decode(0xE000_ED38, 0x2003_0000), "BFAR (address:20030000)");
+    }
+
+    #[test]
+    fn security_registers() {
+        assert_eq!(
+            decode(0xE000_EDF0, 0x0013_0001),
+            "DHCSR (s_restart_st:0, s_reset_st:0, s_sde:1, s_lockup:0, s_halt:1, s_regrdy:1, c_halt:0, c_debugen:1)"
+        );
+        assert_eq!(
+            decode(0xE000_EDF0, 0x0401_0001),
+            "DHCSR (s_restart_st:1, s_reset_st:0, s_sde:0, s_lockup:0, s_halt:0, s_regrdy:1, c_halt:0, c_debugen:1)"
+        );
+        assert_eq!(
+            decode(0xE000_EE08, 0x0001_0003),
+            "DSCSR (cdskey:0, cds:1, sbrsel:1, sbrselen:1)"
+        );
+        assert_eq!(
+            decode(0xE000_EE04, 0x0000_0003),
+            "DAUTHCTRL (intspniden:0, spnidensel:0, intspiden:1, spidensel:1)"
+        );
+        assert_eq!(
+            decode(0xE000_EFB8, 0x0000_00EF),
+            "DAUTHSTATUS (sid:disabled, snid:enabled, nsid:enabled, nsnid:enabled)"
+        );
+        assert!(!Dauthstatus(0x0000_00EF).secure_debug());
+    }
+
+    #[test]
+    fn keyed_writes() {
+        // The DBGKEY bits would read as S_LOCKUP, S_SDE, ...
+        assert_eq!(
+            decode_write(0xE000_EDF0, 0xA05F_0001),
+            "DHCSR (c_snapstall:0, c_maskints:0, c_step:0, c_halt:0, c_debugen:1)"
+        );
+        assert_eq!(
+            decode_write(0xE000_EDF0, 0x0000_0003),
+            "DHCSR (ignored, dbgkey:0000, c_snapstall:0, c_maskints:0, c_step:0, c_halt:1, c_debugen:1)"
+        );
+        assert_eq!(
+            decode_write(0xE000_EDF0, 0xA05F_0023),
+            "DHCSR (c_snapstall:1, c_maskints:0, c_step:0, c_halt:1, c_debugen:1)"
+        );
+        assert_eq!(
+            decode_write(0xE000_ED0C, 0x05FA_0004),
+            "AIRCR (prigroup:0, sysresetreq:1, vectclractive:0, vectreset:0)"
+        );
+        assert_eq!(
+            decode_write(0xE000_ED0C, 0xFA05_0004),
+            "AIRCR (ignored, vectkey:FA05, prigroup:0, sysresetreq:1, vectclractive:0, vectreset:0)"
+        );
+        assert_eq!(
+            decode_write(0xE000_ED0C, 0x05FA_0500),
+            "AIRCR (prigroup:5, sysresetreq:0, vectclractive:0, vectreset:0)"
+        );
+        assert_eq!(
+            decode(0xE000_ED0C, 0xFA05_0300),
+            "AIRCR (endianness:0, prigroup:3)"
+        );
+    }
+}
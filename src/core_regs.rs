@@ -0,0 +1,160 @@
+//! Pairing of DCRSR and DCRDR memory accesses into core register transfers
+
+use crate::arm_regs::{ArmRegister, CoreRegister};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A read or write of a core register by the debugger
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CoreRegisterAccess {
+    pub ap: ApAddress,
+    pub direction: Direction,
+    pub register: CoreRegister,
+    /// The transferred value, unknown for a write when DCRDR wasn't accessed
+    /// before
+    pub value: Option<u32>,
+}
+
+impl fmt::Display for CoreRegisterAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Read => write!(f, "read core register {}", self.register)?,
+            Direction::Write => write!(f, "write core register {}", self.register)?,
+        }
+        match self.value {
+            Some(value) => write!(f, " = 0x{:08X}", value),
+            None => write!(f, " = ?"),
+        }
+    }
+}
+
+/// Transfer state of one MEM-AP
+#[derive(Copy, Clone, Default, Debug)]
+struct Transfer {
+    /// Register selected by a DCRSR read request, waiting for the DCRDR read
+    pending_read: Option<CoreRegister>,
+    /// Last DCRDR value seen
+    dcrdr: Option<u32>,
+}
+
+/// Tracks DCRSR and DCRDR accesses per MEM-AP:
+///
+/// - a core register read is a DCRSR write with REGWnR clear, followed by a
+///   DCRDR read once DHCSR.S_REGRDY is set
+/// - a core register write is a DCRDR write followed by a DCRSR write with
+///   REGWnR set
+#[derive(Clone, Debug, Default)]
+pub struct CoreRegisterTracker {
+    transfers: BTreeMap<ApAddress, Transfer>,
+}
+
+impl CoreRegisterTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the core register access it
+    /// completed, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<CoreRegisterAccess> {
+        let transfer = self.transfers.entry(ap).or_default();
+        match (access.register?, direction) {
+            (ArmRegister::Dcrsr(dcrsr), Direction::Write) if dcrsr.regwnr() => {
+                transfer.pending_read = None;
+                Some(CoreRegisterAccess {
+                    ap,
+                    direction: Direction::Write,
+                    register: dcrsr.core_register(),
+                    value: transfer.dcrdr,
+                })
+            }
+            (ArmRegister::Dcrsr(dcrsr), Direction::Write) => {
+                transfer.pending_read = Some(dcrsr.core_register());
+                None
+            }
+            (ArmRegister::Dcrdr(dcrdr), Direction::Write) => {
+                transfer.dcrdr = Some(dcrdr.0);
+                None
+            }
+            (ArmRegister::Dcrdr(dcrdr), Direction::Read) => {
+                transfer.dcrdr = Some(dcrdr.0);
+                let register = transfer.pending_read.take()?;
+                Some(CoreRegisterAccess {
+                    ap,
+                    direction: Direction::Read,
+                    register,
+                    value: Some(dcrdr.0),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::track;
+
+    #[test]
+    fn read_and_write_core_registers() {
+        let mut tracker = CoreRegisterTracker::new();
+        let accesses = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000002",
+                // Read PC
+                "AP WR A:1 ACK:1 OK Data:hE000EDF4",
+                "AP WR A:3 ACK:1 OK Data:h0000000F",
+                "AP WR A:1 ACK:1 OK Data:hE000EDF0",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h00030003",
+                "AP WR A:1 ACK:1 OK Data:hE000EDF8",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h08000123",
+                // Write SP
+                "AP WR A:3 ACK:1 OK Data:h20001000",
+                "AP WR A:1 ACK:1 OK Data:hE000EDF4",
+                "AP WR A:3 ACK:1 OK Data:h0001000D",
+                // Read of DCRDR without a pending request
+                "AP WR A:1 ACK:1 OK Data:hE000EDF8",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h20001000",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(accesses.len(), 2);
+        assert_eq!(accesses[0].direction, Direction::Read);
+        assert_eq!(accesses[0].register, CoreRegister::PC);
+        assert_eq!(accesses[0].value, Some(0x0800_0123));
+        assert_eq!(
+            accesses[0].to_string(),
+            "read core register PC = 0x08000123"
+        );
+        assert_eq!(accesses[1].direction, Direction::Write);
+        assert_eq!(accesses[1].register, CoreRegister::SP);
+        assert_eq!(
+            accesses[1].to_string(),
+            "write core register SP = 0x20001000"
+        );
+    }
+
+    #[test]
+    fn register_names() {
+        let name = |regsel| CoreRegister(regsel).to_string();
+        assert_eq!(name(0), "R0");
+        assert_eq!(name(12), "R12");
+        assert_eq!(name(16), "xPSR");
+        assert_eq!(name(20), "CONTROL/FAULTMASK/BASEPRI/PRIMASK");
+        assert_eq!(name(0x21), "FPSCR");
+        assert_eq!(name(0x5F), "S31");
+        assert_eq!(name(19), "REGSEL:13");
+    }
+}
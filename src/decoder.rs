@@ -0,0 +1,945 @@
+//! Stateful decoding of SWD operations into typed register accesses
+
+use crate::ap_regs::{AddrInc, ApKind, Bd0, Bd1, Bd2, Bd3, Csw, Drw, Idr, Tar};
+use crate::arm_regs::ArmRegister;
+use crate::dp::{ApAddress, DpState, PostedRead, ReadResult};
+use crate::dp_regs::{
+    Abort, BasePtr0, BasePtr1, CtrlStat, Dlcr, Dlpidr, DpIdr, DpIdr1, DpVersion, EventStat, RdBuff,
+    Resend, Select, Select1, TargetId, TargetSel, Wcr,
+};
+use crate::parser::{AccessRegister, Direction, Response, Sequence, SwdOperation};
+use crate::s32k3xx;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Why an operation couldn't be decoded
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Error {
+    /// A DP register access that isn't supported for the current DP state
+    UnhandledDpAccess {
+        direction: Direction,
+        address: u8,
+        /// The selected DP register bank
+        bank: u8,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnhandledDpAccess {
+                direction,
+                address,
+                bank,
+            } => write!(
+                f,
+                "Unhandled SW-DP register access ({:?} of 0x{:02X}, DP bank {})",
+                direction, address, bank
+            ),
+        }
+    }
+}
+
+/// An operation along with its decoded register access
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Decoded {
+    pub op: SwdOperation,
+    /// The decoded register access, only available for OK acknowledged operations
+    /// and TARGETSEL writes
+    pub access: Option<Access>,
+    /// The result of a previously posted AP read, returned by this operation
+    /// (an AP read or a RDBUFF read)
+    pub read_result: Option<ApAccess>,
+    /// Why the operation couldn't be decoded, the DP state is updated and
+    /// decoding continues regardless
+    pub error: Option<Error>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Access {
+    Dp(DpRegister),
+    /// An AP register write
+    Ap(ApAccess),
+    /// An AP register read request, the data returned along with it belongs
+    /// to the previous AP read
+    ApRead(PostedRead),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DpRegister {
+    DpIdr(DpIdr),
+    DpIdr1(DpIdr1),
+    BasePtr0(BasePtr0),
+    BasePtr1(BasePtr1),
+    Abort(Abort),
+    CtrlStat(CtrlStat),
+    Wcr(Wcr),
+    Dlcr(Dlcr),
+    TargetId(TargetId),
+    Dlpidr(Dlpidr),
+    EventStat(EventStat),
+    Select(Select),
+    Select1(Select1),
+    Resend(Resend),
+    RdBuff(RdBuff),
+    TargetSel(TargetSel),
+}
+
+impl DpRegister {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DpRegister::DpIdr(_) => DpIdr::NAME,
+            DpRegister::DpIdr1(_) => DpIdr1::NAME,
+            DpRegister::BasePtr0(_) => BasePtr0::NAME,
+            DpRegister::BasePtr1(_) => BasePtr1::NAME,
+            DpRegister::Abort(_) => Abort::NAME,
+            DpRegister::CtrlStat(_) => CtrlStat::NAME,
+            DpRegister::Wcr(_) => Wcr::NAME,
+            DpRegister::Dlcr(_) => Dlcr::NAME,
+            DpRegister::TargetId(_) => TargetId::NAME,
+            DpRegister::Dlpidr(_) => Dlpidr::NAME,
+            DpRegister::EventStat(_) => EventStat::NAME,
+            DpRegister::Select(_) => Select::NAME,
+            DpRegister::Select1(_) => Select1::NAME,
+            DpRegister::Resend(_) => Resend::NAME,
+            DpRegister::RdBuff(_) => RdBuff::NAME,
+            DpRegister::TargetSel(_) => TargetSel::NAME,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ApAccess {
+    /// The selected AP
+    pub ap: ApAddress,
+    /// AP register offset, APBANKSEL and A[3:2] for ADIv5 APs
+    pub address: u16,
+    pub register: ApRegister,
+    /// The target memory access performed through a MEM-AP data register
+    pub memory: Option<MemoryAccess>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ApRegister {
+    Csw(Csw),
+    Tar(Tar),
+    Drw(Drw),
+    Bd0(Bd0),
+    Bd1(Bd1),
+    Bd2(Bd2),
+    Bd3(Bd3),
+    Idr(Idr),
+    /// Not a known MEM-AP register, or the AP isn't a MEM-AP
+    Unknown(u32),
+}
+
+impl ApRegister {
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            ApRegister::Csw(_) => Csw::NAME,
+            ApRegister::Tar(_) => Tar::NAME,
+            ApRegister::Drw(_) => Drw::NAME,
+            ApRegister::Bd0(_) => Bd0::NAME,
+            ApRegister::Bd1(_) => Bd1::NAME,
+            ApRegister::Bd2(_) => Bd2::NAME,
+            ApRegister::Bd3(_) => Bd3::NAME,
+            ApRegister::Idr(_) => Idr::NAME,
+            ApRegister::Unknown(_) => return None,
+        })
+    }
+
+    /// The raw register value
+    pub fn value(&self) -> u32 {
+        match self {
+            ApRegister::Csw(r) => r.0,
+            ApRegister::Tar(r) => r.0,
+            ApRegister::Drw(r) => r.0,
+            ApRegister::Bd0(r) => r.0,
+            ApRegister::Bd1(r) => r.0,
+            ApRegister::Bd2(r) => r.0,
+            ApRegister::Bd3(r) => r.0,
+            ApRegister::Idr(r) => r.0,
+            ApRegister::Unknown(data) => *data,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MemoryAccess {
+    pub address: u32,
+    pub value: u32,
+    /// Access size in bytes, from CSW when known
+    pub size: Option<u32>,
+    /// A packed DRW transfer, moving a full word of `size` units
+    pub packed: bool,
+    /// Distance to the next address of a sequential run, the TAR
+    /// auto-increment for DRW or the register spacing for BDn
+    pub stride: Option<u32>,
+    /// The decoded value, if the address is a known register
+    pub register: Option<ArmRegister>,
+}
+
+/// The effect of a non-packet line sequence on the decoding state
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Event {
+    /// Every DP entered its reset state, pending posted reads are lost
+    LineReset,
+    /// SWD was selected, from JTAG or dormant state, resetting every DP
+    SwdSelected,
+    /// The wire left SWD, for dormant state or another protocol
+    SwdDeselected,
+    /// Dormant state selection alert, an activation code follows
+    SelectionAlert,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::LineReset => f.write_str("DP reset state"),
+            Event::SwdSelected => f.write_str("SWD selected, DP state reset"),
+            Event::SwdDeselected => f.write_str("SWD deselected"),
+            Event::SelectionAlert => f.write_str("awaiting activation code"),
+        }
+    }
+}
+
+/// Decoding state of a single target, multi-drop systems have one per
+/// TARGETSEL value
+#[derive(Clone, Debug, Default)]
+pub struct Target {
+    dp: DpState,
+    observed_aps: BTreeSet<ApAddress>,
+}
+
+impl Target {
+    fn new(dp: DpState) -> Self {
+        Self {
+            dp,
+            observed_aps: BTreeSet::new(),
+        }
+    }
+
+    pub fn dp(&self) -> &DpState {
+        &self.dp
+    }
+
+    /// The APs selected so far
+    pub fn observed_aps(&self) -> &BTreeSet<ApAddress> {
+        &self.observed_aps
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    /// Use ADIv6 AP addressing for every target, see [`DpState::adiv6`]
+    adiv6: bool,
+    /// The selected target, `None` until a TARGETSEL write is seen and again
+    /// after a line reset deselected it
+    target: Option<TargetSel>,
+    /// Always contains an entry for the selected target
+    targets: BTreeMap<Option<TargetSel>, Target>,
+    /// The last line was a line reset, TARGETSEL is only accepted then
+    line_reset: bool,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self {
+            adiv6: false,
+            target: None,
+            targets: BTreeMap::from([(None, Target::default())]),
+            line_reset: false,
+        }
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decoder using ADIv6 AP addressing from the start, for captures that
+    /// don't include the DPv3 DPIDR read
+    pub fn adiv6() -> Self {
+        Self {
+            adiv6: true,
+            target: None,
+            targets: BTreeMap::from([(None, Target::new(DpState::adiv6()))]),
+            line_reset: false,
+        }
+    }
+
+    /// The selected target, `None` for single-drop captures or while no
+    /// target is selected
+    pub fn target(&self) -> Option<TargetSel> {
+        self.target
+    }
+
+    /// Whether a TARGETSEL write was seen, i.e. this is a multi-drop capture
+    pub fn is_multi_drop(&self) -> bool {
+        self.targets.keys().any(Option::is_some)
+    }
+
+    /// Every target seen so far, the state while no target is selected,
+    /// before the first TARGETSEL write, is keyed by `None`
+    pub fn targets(&self) -> impl Iterator<Item = (Option<TargetSel>, &Target)> {
+        self.targets.iter().map(|(target, state)| (*target, state))
+    }
+
+    fn current(&self) -> &Target {
+        &self.targets[&self.target]
+    }
+
+    fn current_mut(&mut self) -> &mut Target {
+        self.targets.entry(self.target).or_default()
+    }
+
+    fn select_target(&mut self, target: TargetSel) {
+        let dp = if self.adiv6 {
+            DpState::adiv6()
+        } else {
+            DpState::new()
+        };
+        self.target = Some(target);
+        self.targets
+            .entry(self.target)
+            .or_insert_with(|| Target::new(dp));
+    }
+
+    /// The DP state of the selected target
+    pub fn dp(&self) -> &DpState {
+        &self.current().dp
+    }
+
+    /// The identity of an AP of the selected target, once its IDR was read
+    pub fn ap_kind(&self, ap: ApAddress) -> Option<ApKind> {
+        self.dp().ap(ap)?.idr.map(|idr| idr.kind())
+    }
+
+    /// Whether an AP is a MEM-AP, by its identity when known, falls back to
+    /// the known S32K3xx vendor APs otherwise
+    pub fn is_mem_ap(&self, ap: ApAddress) -> bool {
+        match (self.ap_kind(ap), ap) {
+            (Some(kind), _) => kind.is_mem_ap(),
+            (None, ApAddress::Apsel(apsel)) => !s32k3xx::is_vendor_ap(apsel),
+            (None, ApAddress::Base(_)) => true,
+        }
+    }
+
+    /// The APs of the selected target selected so far
+    pub fn observed_aps(&self) -> &BTreeSet<ApAddress> {
+        self.current().observed_aps()
+    }
+
+    pub fn decode(&mut self, op: SwdOperation) -> Decoded {
+        let after_line_reset = std::mem::take(&mut self.line_reset);
+        let mut error = None;
+        match Self::targetsel(&op) {
+            Some(targetsel) if after_line_reset => {
+                self.select_target(targetsel);
+                return Decoded {
+                    op,
+                    access: Some(Access::Dp(DpRegister::TargetSel(targetsel))),
+                    read_result: None,
+                    error: None,
+                };
+            }
+            // OK acknowledged writes are reported by decode_dp
+            Some(_) if op.data().is_none() => {
+                error = Some(Error::UnhandledDpAccess {
+                    direction: op.direction,
+                    address: TargetSel::ADDRESS,
+                    bank: self.dp().dp_bank(),
+                });
+            }
+            _ => (),
+        }
+
+        let mut access = match (op.data(), op.access) {
+            (Some(data), AccessRegister::DebugPort) => match self.decode_dp(&op, data) {
+                Ok(reg) => Some(Access::Dp(reg)),
+                Err(e) => {
+                    error = Some(e);
+                    None
+                }
+            },
+            (Some(data), AccessRegister::AccessPort) if op.direction == Direction::Write => {
+                let dp = self.dp();
+                let ap = dp.selected_ap();
+                let address = dp.ap_address(op.address_2_3);
+                let memory_address = dp.memory_address(ap, address);
+                let csw = dp.ap(ap).and_then(|state| state.csw);
+                Some(Access::Ap(self.decode_ap(
+                    ap,
+                    address,
+                    data,
+                    Direction::Write,
+                    memory_address,
+                    csw,
+                )))
+            }
+            _ => None,
+        };
+
+        let read_result = self
+            .current_mut()
+            .dp
+            .update(&op)
+            .map(|ReadResult { read, data }| {
+                self.decode_ap(
+                    read.ap,
+                    read.address,
+                    data,
+                    Direction::Read,
+                    read.memory_address,
+                    read.csw,
+                )
+            });
+
+        if op.data().is_some()
+            && op.access == AccessRegister::AccessPort
+            && op.direction == Direction::Read
+        {
+            access = self.dp().posted_read().map(Access::ApRead);
+        }
+
+        // ADIv6 SELECT writes also just switch DP banks, so those APs are
+        // only observed once accessed
+        let observed = match access {
+            Some(Access::Dp(DpRegister::Select(_))) if !self.dp().is_adiv6() => {
+                Some(self.dp().selected_ap())
+            }
+            Some(Access::Ap(ApAccess { ap, .. })) | Some(Access::ApRead(PostedRead { ap, .. })) => {
+                Some(ap)
+            }
+            _ => None,
+        };
+        if let Some(ap) = observed {
+            self.current_mut().observed_aps.insert(ap);
+        }
+
+        Decoded {
+            op,
+            access,
+            read_result,
+            error,
+        }
+    }
+
+    /// Apply a non-packet line sequence, these are seen by every target on the wire
+    pub fn sequence(&mut self, sequence: Sequence) -> Event {
+        let event = match sequence {
+            Sequence::LineReset => Event::LineReset,
+            Sequence::JtagToSwd => Event::SwdSelected,
+            Sequence::Activation(Sequence::SWD_ACTIVATION_CODE) => Event::SwdSelected,
+            Sequence::Activation(_) | Sequence::SwdToDormant => Event::SwdDeselected,
+            Sequence::SelectionAlert => Event::SelectionAlert,
+        };
+        for target in self.targets.values_mut() {
+            match event {
+                Event::LineReset => target.dp.line_reset(),
+                Event::SwdSelected => target.dp.protocol_reset(),
+                Event::SwdDeselected | Event::SelectionAlert => (),
+            }
+        }
+        // A line reset deselects every multi-drop target until the next
+        // TARGETSEL write
+        if event == Event::LineReset {
+            self.target = None;
+        }
+        self.line_reset = event == Event::LineReset;
+        event
+    }
+
+    /// TARGETSEL is a DP write to 0x0C following a line reset, its ACK isn't
+    /// driven so the data phase is taken regardless of the response
+    fn targetsel(op: &SwdOperation) -> Option<TargetSel> {
+        if op.access != AccessRegister::DebugPort
+            || op.direction != Direction::Write
+            || op.address_2_3 << 2 != TargetSel::ADDRESS
+        {
+            return None;
+        }
+        match op.response {
+            Response::Ok(data) | Response::NoAck(Some(data)) => Some(TargetSel(data)),
+            _ => None,
+        }
+    }
+
+    fn decode_dp(&mut self, op: &SwdOperation, data: u32) -> Result<DpRegister, Error> {
+        let address = op.address_2_3 << 2;
+        let is_read = op.direction == Direction::Read;
+        let dp_bank = self.dp().dp_bank();
+        let version = self.dp().version();
+        // Bank 1 keeps its ADIv5.1 name unless the DPIDR says otherwise
+        let has_dlcr = version.is_some_and(|v| v >= DpVersion::V1);
+        // Without a DPIDR read assume banks 2-4 are present
+        let dpv2 = version.unwrap_or(DpVersion::V2) >= DpVersion::V2;
+        // Banked 0x00 registers are only assumed once the DPIDR says so, or
+        // when decoding as ADIv6
+        let dpv3 = self.dp().is_adiv6();
+        Ok(match address {
+            // 0x00, banked from DPv3
+            DpIdr1::ADDRESS if is_read && dpv3 && dp_bank == DpIdr1::BANK => {
+                DpRegister::DpIdr1(DpIdr1(data))
+            }
+            BasePtr0::ADDRESS if is_read && dpv3 && dp_bank == BasePtr0::BANK => {
+                DpRegister::BasePtr0(BasePtr0(data))
+            }
+            BasePtr1::ADDRESS if is_read && dpv3 && dp_bank == BasePtr1::BANK => {
+                DpRegister::BasePtr1(BasePtr1(data))
+            }
+            DpIdr::ADDRESS if is_read && (!dpv3 || dp_bank == 0) => DpRegister::DpIdr(DpIdr(data)),
+            Abort::ADDRESS if !is_read => DpRegister::Abort(Abort(data)),
+
+            // 0x04
+            CtrlStat::ADDRESS if dp_bank == CtrlStat::BANK => DpRegister::CtrlStat(CtrlStat(data)),
+            Dlcr::ADDRESS if dp_bank == Dlcr::BANK && has_dlcr => DpRegister::Dlcr(Dlcr(data)),
+            Wcr::ADDRESS if dp_bank == Wcr::BANK => DpRegister::Wcr(Wcr(data)),
+            TargetId::ADDRESS if dp_bank == TargetId::BANK && is_read && dpv2 => {
+                DpRegister::TargetId(TargetId(data))
+            }
+            Dlpidr::ADDRESS if dp_bank == Dlpidr::BANK && is_read && dpv2 => {
+                DpRegister::Dlpidr(Dlpidr(data))
+            }
+            EventStat::ADDRESS if dp_bank == EventStat::BANK && is_read && dpv2 => {
+                DpRegister::EventStat(EventStat(data))
+            }
+            Select1::ADDRESS if dp_bank == Select1::BANK && self.dp().is_adiv6() => {
+                DpRegister::Select1(Select1(data))
+            }
+
+            // 0x08
+            Select::ADDRESS if !is_read => DpRegister::Select(Select(data)),
+            Resend::ADDRESS if is_read => DpRegister::Resend(Resend(data)),
+
+            // 0x0C
+            RdBuff::ADDRESS if is_read => DpRegister::RdBuff(RdBuff(data)),
+
+            _ => {
+                return Err(Error::UnhandledDpAccess {
+                    direction: op.direction,
+                    address,
+                    bank: dp_bank,
+                })
+            }
+        })
+    }
+
+    fn decode_ap(
+        &self,
+        ap: ApAddress,
+        address: u16,
+        data: u32,
+        direction: Direction,
+        memory_address: Option<u32>,
+        csw: Option<Csw>,
+    ) -> ApAccess {
+        let register = match ap.register(address) {
+            Some(Idr::ADDRESS) => ApRegister::Idr(Idr(data)),
+            Some(register) if self.is_mem_ap(ap) => match register {
+                Tar::ADDRESS => ApRegister::Tar(Tar(data)),
+                Csw::ADDRESS => ApRegister::Csw(Csw(data)),
+                Drw::ADDRESS => ApRegister::Drw(Drw(data)),
+                Bd0::ADDRESS => ApRegister::Bd0(Bd0(data)),
+                Bd1::ADDRESS => ApRegister::Bd1(Bd1(data)),
+                Bd2::ADDRESS => ApRegister::Bd2(Bd2(data)),
+                Bd3::ADDRESS => ApRegister::Bd3(Bd3(data)),
+                _ => ApRegister::Unknown(data),
+            },
+            _ => ApRegister::Unknown(data),
+        };
+
+        let stride = match register {
+            ApRegister::Drw(_) => csw.and_then(|csw| csw.tar_increment()),
+            _ => Some(4),
+        };
+        let packed = matches!(register, ApRegister::Drw(_))
+            && csw.is_some_and(|csw| csw.addr_inc() == AddrInc::Packed);
+        let memory = match register {
+            ApRegister::Unknown(_) => None,
+            _ => memory_address.map(|address| MemoryAccess {
+                address,
+                value: data,
+                size: csw.and_then(|csw| csw.size_bytes()),
+                packed,
+                stride,
+                register: ArmRegister::decode(address, data, direction),
+            }),
+        };
+
+        ApAccess {
+            ap,
+            address,
+            register,
+            memory,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn op(s: &str) -> SwdOperation {
+        parser::parse(s).unwrap().1
+    }
+
+    #[test]
+    fn not_acknowledged_ops_are_not_decoded() {
+        let mut d = Decoder::new();
+        let decoded = d.decode(op("DP WR A:2 ACK:2 Wait"));
+        assert_eq!(decoded.access, None);
+    }
+
+    #[test]
+    fn drw_memory_access() {
+        let mut d = Decoder::new();
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000000"));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDF0"));
+        let decoded = d.decode(op("AP WR A:3 ACK:1 OK Data:hA05F0001"));
+        let Some(Access::Ap(ap)) = decoded.access else {
+            panic!("Expected an AP access");
+        };
+        assert_eq!(ap.ap, ApAddress::Apsel(4));
+        assert_eq!(ap.address, Drw::ADDRESS.into());
+        let mem = ap.memory.unwrap();
+        assert_eq!(mem.address, 0xE000_EDF0);
+        assert!(matches!(mem.register, Some(ArmRegister::DhcsrWrite(_))));
+        assert_eq!(
+            d.observed_aps().iter().copied().collect::<Vec<_>>(),
+            [ApAddress::Apsel(4)]
+        );
+    }
+
+    #[test]
+    fn posted_read_results_are_reattributed() {
+        let mut d = Decoder::new();
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000000"));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDF0"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000010"));
+
+        let first = d.decode(op("AP RD A:0 ACK:1 OK Data:h40000000"));
+        let dhcsr_read = PostedRead {
+            ap: ApAddress::Apsel(4),
+            address: Bd0::ADDRESS.into(),
+            memory_address: Some(0xE000_EDF0),
+            csw: None,
+        };
+        assert_eq!(first.access, Some(Access::ApRead(dhcsr_read)));
+        assert_eq!(first.read_result, None);
+
+        let second = d.decode(op("AP RD A:0 ACK:1 OK Data:h00030003"));
+        let result = second.read_result.unwrap();
+        assert_eq!(result.register, ApRegister::Bd0(Bd0(0x0003_0003)));
+        let mem = result.memory.unwrap();
+        assert_eq!(mem.address, 0xE000_EDF0);
+        let Some(ArmRegister::Dhcsr(dhcsr)) = mem.register else {
+            panic!("Expected DHCSR");
+        };
+        assert!(dhcsr.s_halt());
+
+        let rdbuff = d.decode(op("DP RD A:3 ACK:1 OK Data:h00030003"));
+        assert_eq!(
+            rdbuff.read_result.map(|r| r.register.value()),
+            Some(0x0003_0003)
+        );
+        let rdbuff = d.decode(op("DP RD A:3 ACK:1 OK Data:h00030003"));
+        assert_eq!(rdbuff.read_result, None);
+    }
+
+    #[test]
+    fn ap_identity_overrides_s32k3xx_fallback() {
+        let mut d = Decoder::new();
+        assert!(!d.is_mem_ap(ApAddress::Apsel(s32k3xx::MDM_AP_ID)));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h060000F0"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+        let idr = d.decode(op("DP RD A:3 ACK:1 OK Data:h84770001"));
+        assert_eq!(
+            idr.read_result.map(|r| r.register),
+            Some(ApRegister::Idr(Idr(0x8477_0001)))
+        );
+        assert!(d.is_mem_ap(ApAddress::Apsel(s32k3xx::MDM_AP_ID)));
+        assert!(d
+            .ap_kind(ApAddress::Apsel(s32k3xx::MDM_AP_ID))
+            .unwrap()
+            .is_mem_ap());
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h010000F0"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+        d.decode(op("DP RD A:3 ACK:1 OK Data:h001C0000"));
+        assert!(!d.is_mem_ap(ApAddress::Apsel(s32k3xx::APB_AP_ID)));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h01000000"));
+        d.decode(op("AP RD A:1 ACK:1 OK Data:h00000000"));
+        let tar = d.decode(op("DP RD A:3 ACK:1 OK Data:h00000000"));
+        assert_eq!(
+            tar.read_result.map(|r| r.register),
+            Some(ApRegister::Unknown(0))
+        );
+    }
+
+    #[test]
+    fn unhandled_dp_access() {
+        let mut d = Decoder::new();
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000007"));
+        let decoded = d.decode(op("DP WR A:1 ACK:1 OK Data:h00000000"));
+        assert_eq!(decoded.access, None);
+        assert_eq!(
+            decoded.error,
+            Some(Error::UnhandledDpAccess {
+                direction: Direction::Write,
+                address: 0x04,
+                bank: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn decoding_continues_after_errors() {
+        let mut d = Decoder::new();
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000000"));
+        d.decode(op("AP WR A:0 ACK:1 OK Data:h03000002"));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDF0"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000007"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:h00000000"));
+        assert!(decoded.error.is_some());
+        // The DP state is still updated around the failed access
+        let decoded = d.decode(op("DP RD A:3 ACK:1 OK Data:h00030003"));
+        assert_eq!(decoded.error, None);
+        assert_eq!(
+            decoded
+                .read_result
+                .and_then(|r| r.memory)
+                .map(|m| m.address),
+            Some(0xE000_EDF0)
+        );
+    }
+
+    #[test]
+    fn dpv2_bank_registers() {
+        let mut d = Decoder::new();
+        d.decode(op("DP RD A:0 ACK:1 OK Data:h6BA02477"));
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000001"));
+        let decoded = d.decode(op("DP WR A:1 ACK:1 OK Data:h00000040"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::Dlcr(Dlcr(0x40))))
+        );
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000002"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:h01002927"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::TargetId(TargetId(0x0100_2927))))
+        );
+        // TARGETID is read-only
+        let decoded = d.decode(op("DP WR A:1 ACK:1 OK Data:h01002927"));
+        assert!(decoded.error.is_some());
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000003"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:h00000001"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::Dlpidr(Dlpidr(1))))
+        );
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000004"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:h00000000"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::EventStat(EventStat(0))))
+        );
+
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000000"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:hF0000040"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::CtrlStat(CtrlStat(0xF000_0040))))
+        );
+    }
+
+    #[test]
+    fn dpv1_has_no_targetid() {
+        let mut d = Decoder::new();
+        d.decode(op("DP RD A:0 ACK:1 OK Data:h2BA01477"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000002"));
+        let decoded = d.decode(op("DP RD A:1 ACK:1 OK Data:h00000000"));
+        assert_eq!(decoded.access, None);
+        assert!(decoded.error.is_some());
+    }
+
+    #[test]
+    fn targetsel_selects_target() {
+        let mut d = Decoder::new();
+        assert_eq!(d.target(), None);
+        d.sequence(Sequence::LineReset);
+        let decoded = d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::TargetSel(TargetSel(0x0100_2927))))
+        );
+        assert_eq!(decoded.error, None);
+        assert_eq!(d.target(), Some(TargetSel(0x0100_2927)));
+    }
+
+    #[test]
+    fn targetsel_requires_line_reset() {
+        let mut d = Decoder::new();
+        for line in [
+            "DP WR A:3 ACK:7 Data:h01002927",
+            "DP WR A:3 ACK:1 OK Data:h01002927",
+        ] {
+            let decoded = d.decode(op(line));
+            assert_eq!(decoded.access, None);
+            assert!(matches!(
+                decoded.error,
+                Some(Error::UnhandledDpAccess {
+                    direction: Direction::Write,
+                    address: 0x0C,
+                    ..
+                })
+            ));
+        }
+        // Only the operation right after the line reset
+        d.sequence(Sequence::LineReset);
+        d.decode(op("DP RD A:0 ACK:7 Data:h00000000"));
+        d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        assert_eq!(d.target(), None);
+        assert!(!d.is_multi_drop());
+    }
+
+    #[test]
+    fn per_target_state() {
+        let mut d = Decoder::new();
+        // Core 0, read DHCSR
+        d.sequence(Sequence::LineReset);
+        d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000000"));
+        d.decode(op("AP WR A:0 ACK:1 OK Data:h03000002"));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDF0"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+
+        // Core 1, read DEMCR on the same AP number
+        d.sequence(Sequence::LineReset);
+        d.decode(op("DP WR A:3 ACK:7 Data:h11002927"));
+        assert_eq!(d.dp().posted_read(), None);
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000000"));
+        d.decode(op("AP WR A:0 ACK:1 OK Data:h03000002"));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDFC"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+        let decoded = d.decode(op("DP RD A:3 ACK:1 OK Data:h01000000"));
+        let mem = decoded.read_result.and_then(|r| r.memory).unwrap();
+        assert_eq!(mem.address, 0xE000_EDFC);
+
+        // Back to core 0, its TAR is untouched while the line reset lost
+        // the posted read
+        d.sequence(Sequence::LineReset);
+        d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        assert_eq!(d.dp().posted_read(), None);
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+        let decoded = d.decode(op("DP RD A:3 ACK:1 OK Data:h00030003"));
+        let mem = decoded.read_result.and_then(|r| r.memory).unwrap();
+        assert_eq!(mem.address, 0xE000_EDF0);
+
+        let targets: Vec<_> = d.targets().map(|(t, _)| t).collect();
+        assert_eq!(
+            targets,
+            [
+                None,
+                Some(TargetSel(0x0100_2927)),
+                Some(TargetSel(0x1100_2927))
+            ]
+        );
+    }
+
+    #[test]
+    fn connection_sequences() {
+        let mut d = Decoder::new();
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h04000000"));
+        d.decode(op("AP RD A:3 ACK:1 OK Data:h00000000"));
+
+        assert_eq!(d.sequence(Sequence::LineReset), Event::LineReset);
+        assert_eq!(d.dp().posted_read(), None);
+        assert_eq!(d.dp().selected_ap(), ApAddress::Apsel(4));
+
+        assert_eq!(d.sequence(Sequence::SwdToDormant), Event::SwdDeselected);
+        assert_eq!(d.sequence(Sequence::SelectionAlert), Event::SelectionAlert);
+        assert_eq!(d.sequence(Sequence::Activation(0x1A)), Event::SwdSelected);
+        assert_eq!(d.dp().selected_ap(), ApAddress::Apsel(0));
+        assert_eq!(d.sequence(Sequence::Activation(0x0A)), Event::SwdDeselected);
+        assert_eq!(d.sequence(Sequence::JtagToSwd), Event::SwdSelected);
+    }
+
+    #[test]
+    fn line_reset_deselects_target() {
+        let mut d = Decoder::new();
+        d.sequence(Sequence::LineReset);
+        d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000002"));
+        assert_eq!(d.target(), Some(TargetSel(0x0100_2927)));
+        assert_eq!(d.dp().dp_bank(), 2);
+
+        d.sequence(Sequence::LineReset);
+        assert_eq!(d.target(), None);
+        assert!(d.is_multi_drop());
+        d.decode(op("DP WR A:3 ACK:7 Data:h01002927"));
+        assert_eq!(d.dp().dp_bank(), 0);
+        assert_eq!(d.dp().select(), Select(0x0000_0000));
+    }
+
+    #[test]
+    fn adiv6_mem_ap() {
+        let mut d = Decoder::adiv6();
+        let decoded = d.decode(op("DP WR A:2 ACK:1 OK Data:h000D0D00"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::Select(Select(0x000D_0D00))))
+        );
+        let ap = ApAddress::Base(0x000D_0000);
+        assert!(d.observed_aps().is_empty());
+
+        d.decode(op("AP WR A:0 ACK:1 OK Data:h23000052"));
+        assert!(d.observed_aps().contains(&ap));
+        d.decode(op("AP WR A:1 ACK:1 OK Data:hE000EDF0"));
+        let decoded = d.decode(op("AP WR A:3 ACK:1 OK Data:hA05F0003"));
+        let Some(Access::Ap(access)) = decoded.access else {
+            panic!("expected an AP write");
+        };
+        assert_eq!(access.ap, ap);
+        assert_eq!(access.address, 0xD0C);
+        assert_eq!(access.register, ApRegister::Drw(Drw(0xA05F_0003)));
+        assert_eq!(access.memory.map(|m| m.address), Some(0xE000_EDF0));
+
+        // Outside of the MEM-AP register block
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h000D0FC0"));
+        let decoded = d.decode(op("AP WR A:0 ACK:1 OK Data:h00000000"));
+        let Some(Access::Ap(access)) = decoded.access else {
+            panic!("expected an AP write");
+        };
+        assert_eq!(access.address, 0xFC0);
+        assert_eq!(access.register, ApRegister::Unknown(0));
+    }
+
+    #[test]
+    fn dpv3_banked_registers() {
+        let mut d = Decoder::new();
+        d.decode(op("DP RD A:0 ACK:1 OK Data:h6BA03477"));
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000002"));
+        let decoded = d.decode(op("DP RD A:0 ACK:1 OK Data:hE00FF003"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::BasePtr0(BasePtr0(0xE00F_F003))))
+        );
+        d.decode(op("DP WR A:2 ACK:1 OK Data:h00000005"));
+        let decoded = d.decode(op("DP WR A:1 ACK:1 OK Data:h00000000"));
+        assert_eq!(
+            decoded.access,
+            Some(Access::Dp(DpRegister::Select1(Select1(0))))
+        );
+    }
+}
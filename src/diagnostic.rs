@@ -0,0 +1,48 @@
+//! Problems found while decoding a log, none of them stop decoding
+
+use crate::decoder;
+use std::fmt;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Diagnostic {
+    /// One-based line number within the log
+    pub line: usize,
+    /// The raw line text
+    pub text: String,
+    pub reason: Reason,
+}
+
+impl Diagnostic {
+    pub fn new(line: usize, text: &str, reason: Reason) -> Self {
+        Self {
+            line,
+            text: text.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.reason, self.text)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Reason {
+    Decode(decoder::Error),
+}
+
+impl From<decoder::Error> for Reason {
+    fn from(e: decoder::Error) -> Self {
+        Reason::Decode(e)
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Decode(e) => e.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,825 @@
+//! ADIv5/ADIv6 debug port state model
+
+use crate::ap_regs::{self, Bd0, Bd1, Bd2, Bd3, Csw, Drw, Idr, Tar};
+use crate::dp_regs::{Abort, CtrlStat, DpIdr, DpVersion, RdBuff, Select, Select1};
+use crate::parser::{AccessRegister, Direction, Response, SwdOperation};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Model of the DP state as seen by the target, only OK acknowledged
+/// operations change it
+#[derive(Clone, Debug)]
+pub struct DpState {
+    /// ADIv6 AP addressing regardless of the DPIDR
+    adiv6: bool,
+    dpidr: Option<DpIdr>,
+    select: Select,
+    select1: Select1,
+    ctrl_stat: Option<CtrlStat>,
+    sticky: StickyFlags,
+    posted_read: Option<PostedRead>,
+    aps: BTreeMap<ApAddress, ApState>,
+}
+
+/// Identifies an AP
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ApAddress {
+    /// ADIv5 AP, selected by SELECT.APSEL
+    Apsel(u8),
+    /// ADIv6 AP, by its 4KB aligned base address from SELECT1 and SELECT.ADDR
+    Base(u64),
+}
+
+impl ApAddress {
+    /// The ADIv5 APSEL, if this is an ADIv5 AP
+    pub fn apsel(&self) -> Option<u8> {
+        match self {
+            ApAddress::Apsel(apsel) => Some(*apsel),
+            ApAddress::Base(_) => None,
+        }
+    }
+
+    /// The ADIv5 MEM-AP register address of an AP register offset, `None`
+    /// for ADIv6 offsets outside of the MEM-AP register block
+    pub fn register(&self, offset: u16) -> Option<u8> {
+        match self {
+            ApAddress::Apsel(_) => Some(offset as u8),
+            ApAddress::Base(_) => (offset & 0xF00 == ap_regs::ADIV6_OFFSET).then_some(offset as u8),
+        }
+    }
+}
+
+impl fmt::Display for ApAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApAddress::Apsel(apsel) => write!(f, "{:02X}", apsel),
+            ApAddress::Base(base) if *base <= u32::MAX as u64 => write!(f, "@{:08X}", base),
+            ApAddress::Base(base) => write!(f, "@{:016X}", base),
+        }
+    }
+}
+
+/// The DP sticky error flags, as last observed
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct StickyFlags {
+    pub stickyorun: bool,
+    pub stickycmp: bool,
+    pub stickyerr: bool,
+    pub wdataerr: bool,
+    /// A FAULT response was seen since the flags were last cleared,
+    /// which one of the flags caused it is only known once CTRL/STAT is read
+    pub fault: bool,
+}
+
+impl StickyFlags {
+    pub fn any(&self) -> bool {
+        self.stickyorun || self.stickycmp || self.stickyerr || self.wdataerr || self.fault
+    }
+}
+
+/// An AP read whose result hasn't been returned yet, SWD AP reads are posted
+/// and return the result of the previous AP read
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PostedRead {
+    pub ap: ApAddress,
+    /// AP register offset, APBANKSEL and A[3:2] for ADIv5 APs
+    pub address: u16,
+    /// The target memory address, for MEM-AP data register reads, as of
+    /// when the read was issued
+    pub memory_address: Option<u32>,
+    /// CSW as of when the read was issued, for the size of a memory access
+    pub csw: Option<Csw>,
+}
+
+/// A posted read along with its result
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ReadResult {
+    pub read: PostedRead,
+    pub data: u32,
+}
+
+/// Per-AP register state
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ApState {
+    pub csw: Option<Csw>,
+    /// TAR, including the emulated auto-increments of DRW accesses
+    pub tar: Option<Tar>,
+    /// The AP identity, once its IDR was read
+    pub idr: Option<Idr>,
+}
+
+impl ApState {
+    /// The target memory address of a MEM-AP data register access
+    pub fn memory_address(&self, address: u8) -> Option<u32> {
+        let tar = self.tar?;
+        Some(match address {
+            Drw::ADDRESS => tar.addr(),
+            Bd0::ADDRESS => tar.banked_addr(0),
+            Bd1::ADDRESS => tar.banked_addr(1),
+            Bd2::ADDRESS => tar.banked_addr(2),
+            Bd3::ADDRESS => tar.banked_addr(3),
+            _ => return None,
+        })
+    }
+
+    /// Emulate the TAR auto-increment that follows a DRW access, TAR is
+    /// unknown afterwards when CSW isn't
+    fn auto_increment(&mut self) {
+        self.tar = match self.csw {
+            Some(csw) => self
+                .tar
+                .map(|tar| csw.tar_increment().map_or(tar, |inc| tar.incremented(inc))),
+            None => None,
+        };
+    }
+}
+
+impl Default for DpState {
+    fn default() -> Self {
+        Self {
+            adiv6: false,
+            dpidr: None,
+            select: Select(0),
+            select1: Select1(0),
+            ctrl_stat: None,
+            sticky: StickyFlags::default(),
+            posted_read: None,
+            aps: BTreeMap::new(),
+        }
+    }
+}
+
+impl DpState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// DP state using ADIv6 AP addressing from the start, rather than once a
+    /// DPv3 DPIDR is read
+    pub fn adiv6() -> Self {
+        Self {
+            adiv6: true,
+            ..Self::default()
+        }
+    }
+
+    /// Whether APs are addressed as ADIv6 APs, by base address
+    pub fn is_adiv6(&self) -> bool {
+        self.adiv6 || self.version() == Some(DpVersion::V3)
+    }
+
+    /// The DPIDR, once read
+    pub fn dpidr(&self) -> Option<DpIdr> {
+        self.dpidr
+    }
+
+    /// The DP architecture version, from the DPIDR
+    pub fn version(&self) -> Option<DpVersion> {
+        self.dpidr.map(|dpidr| dpidr.dp_version())
+    }
+
+    pub fn select(&self) -> Select {
+        self.select
+    }
+
+    /// The selected DP register bank for address 0x04, DPv0 only has
+    /// CTRLSEL while later versions have DPBANKSEL
+    pub fn dp_bank(&self) -> u8 {
+        match self.version() {
+            Some(DpVersion::V0) => self.select.ctrlsel() as u8,
+            _ => self.select.dpbanksel() as u8,
+        }
+    }
+
+    pub fn select1(&self) -> Select1 {
+        self.select1
+    }
+
+    /// The AP selected by SELECT, and SELECT1 for ADIv6
+    pub fn selected_ap(&self) -> ApAddress {
+        if self.is_adiv6() {
+            let address =
+                (u64::from(self.select1.addr()) << 32) | u64::from(self.select.adiv6_address());
+            ApAddress::Base(address & !0xFFF)
+        } else {
+            ApAddress::Apsel(self.select.apsel() as u8)
+        }
+    }
+
+    /// AP register offset of an AP access given the current APBANKSEL, or
+    /// SELECT.ADDR[11:4] for ADIv6
+    pub fn ap_address(&self, address_2_3: u8) -> u16 {
+        if self.is_adiv6() {
+            (self.select.adiv6_address() & 0xFF0) as u16 | (u16::from(address_2_3) << 2)
+        } else {
+            mem_ap_address(self.select.apbanksel() as u8, address_2_3).into()
+        }
+    }
+
+    /// The last CTRL/STAT value written or read
+    pub fn ctrl_stat(&self) -> Option<CtrlStat> {
+        self.ctrl_stat
+    }
+
+    pub fn sticky_flags(&self) -> StickyFlags {
+        self.sticky
+    }
+
+    pub fn posted_read(&self) -> Option<PostedRead> {
+        self.posted_read
+    }
+
+    pub fn ap(&self, ap: ApAddress) -> Option<&ApState> {
+        self.aps.get(&ap)
+    }
+
+    pub fn aps(&self) -> impl Iterator<Item = (ApAddress, &ApState)> {
+        self.aps.iter().map(|(address, ap)| (*address, ap))
+    }
+
+    /// The target memory address accessed through a MEM-AP data register
+    pub fn memory_address(&self, ap: ApAddress, address: u16) -> Option<u32> {
+        self.ap(ap)?.memory_address(ap.register(address)?)
+    }
+
+    /// A line reset puts the DP in its reset state, where only a DPIDR read
+    /// or TARGETSEL write is accepted: SELECT.DPBANKSEL (CTRLSEL on DPv0)
+    /// returns to 0 and any posted read result is lost. The other SELECT
+    /// fields, CTRL/STAT and the sticky flags keep their values, the flags are
+    /// only cleared through ABORT or CTRL/STAT
+    pub fn line_reset(&mut self) {
+        self.select.set_dpbanksel(0);
+        self.posted_read = None;
+    }
+
+    /// The SW-DP was (re)selected from JTAG or dormant state, SELECT,
+    /// CTRL/STAT, the sticky flags and any posted read are reset. The DPIDR
+    /// and AP state describe the target itself and are kept
+    pub fn protocol_reset(&mut self) {
+        self.select = Select(0);
+        self.select1 = Select1(0);
+        self.ctrl_stat = None;
+        self.sticky = StickyFlags::default();
+        self.posted_read = None;
+    }
+
+    /// Apply an operation to the state, returns the result of a previously
+    /// posted AP read if the operation completed one
+    pub fn update(&mut self, op: &SwdOperation) -> Option<ReadResult> {
+        let data = match op.response {
+            Response::Ok(data) => data,
+            Response::Fault => {
+                self.sticky.fault = true;
+                return None;
+            }
+            // The target didn't act on the request
+            Response::Wait | Response::NoAck(_) | Response::ParityError(_) => return None,
+        };
+
+        match op.access {
+            AccessRegister::DebugPort => self.update_dp(op, data),
+            AccessRegister::AccessPort => self.update_ap(op, data),
+        }
+    }
+
+    /// Complete the pending posted read, if any, with the returned data
+    fn complete_posted_read(&mut self, data: u32) -> Option<ReadResult> {
+        let read = self.posted_read.take()?;
+        let ap = self.aps.entry(read.ap).or_default();
+        match read.ap.register(read.address) {
+            Some(Csw::ADDRESS) => ap.csw = Some(Csw(data)),
+            Some(Tar::ADDRESS) => ap.tar = Some(Tar(data)),
+            Some(Idr::ADDRESS) => ap.idr = Some(Idr(data)),
+            _ => (),
+        }
+        Some(ReadResult { read, data })
+    }
+
+    fn update_dp(&mut self, op: &SwdOperation, data: u32) -> Option<ReadResult> {
+        let address = op.address_2_3 << 2;
+        match (op.direction, address) {
+            // DPv3 banks DPIDR1 and the base pointers at 0x00
+            (Direction::Read, DpIdr::ADDRESS) if !self.is_adiv6() || self.dp_bank() == 0 => {
+                self.dpidr = Some(DpIdr(data))
+            }
+            (Direction::Write, Abort::ADDRESS) => {
+                let abort = Abort(data);
+                if abort.dapabort() {
+                    self.posted_read = None;
+                }
+                if abort.stkcmpclr() {
+                    self.sticky.stickycmp = false;
+                }
+                if abort.stkerrclr() {
+                    self.sticky.stickyerr = false;
+                }
+                if abort.wderrclr() {
+                    self.sticky.wdataerr = false;
+                }
+                if abort.orunerrclr() {
+                    self.sticky.stickyorun = false;
+                }
+                if !self.sticky.stickycmp
+                    && !self.sticky.stickyerr
+                    && !self.sticky.wdataerr
+                    && !self.sticky.stickyorun
+                {
+                    self.sticky.fault = false;
+                }
+            }
+            (_, CtrlStat::ADDRESS) if self.dp_bank() == 0 => {
+                let ctrl_stat = CtrlStat(data);
+                if op.direction == Direction::Read {
+                    self.sticky = StickyFlags {
+                        stickyorun: ctrl_stat.stickyorun(),
+                        stickycmp: ctrl_stat.stickycmp(),
+                        stickyerr: ctrl_stat.stickyerr(),
+                        wdataerr: ctrl_stat.wdataerr(),
+                        fault: false,
+                    };
+                }
+                self.ctrl_stat = Some(ctrl_stat);
+            }
+            (Direction::Write, Select1::ADDRESS)
+                if self.is_adiv6() && self.dp_bank() == Select1::BANK =>
+            {
+                self.select1 = Select1(data)
+            }
+            (Direction::Write, Select::ADDRESS) => self.select = Select(data),
+            (Direction::Read, RdBuff::ADDRESS) => return self.complete_posted_read(data),
+            _ => (),
+        }
+        None
+    }
+
+    fn update_ap(&mut self, op: &SwdOperation, data: u32) -> Option<ReadResult> {
+        let ap = self.selected_ap();
+        let address = self.ap_address(op.address_2_3);
+        let register = ap.register(address);
+        match op.direction {
+            Direction::Read => {
+                let memory_address = self.memory_address(ap, address);
+                let csw = self.ap(ap).and_then(|state| state.csw);
+                let result = self.complete_posted_read(data);
+                self.posted_read = Some(PostedRead {
+                    ap,
+                    address,
+                    memory_address,
+                    csw,
+                });
+                if register == Some(Drw::ADDRESS) {
+                    self.aps.entry(ap).or_default().auto_increment();
+                }
+                result
+            }
+            Direction::Write => {
+                let ap = self.aps.entry(ap).or_default();
+                match register {
+                    Some(Csw::ADDRESS) => ap.csw = Some(Csw(data)),
+                    Some(Tar::ADDRESS) => ap.tar = Some(Tar(data)),
+                    Some(Drw::ADDRESS) => ap.auto_increment(),
+                    _ => (),
+                }
+                None
+            }
+        }
+    }
+}
+
+fn mem_ap_address(apbanksel: u8, address_2_3: u8) -> u8 {
+    (apbanksel << 4) | (address_2_3 << 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn apply(dp: &mut DpState, lines: &[&str]) {
+        for line in lines {
+            dp.update(&parser::parse(line).unwrap().1);
+        }
+    }
+
+    #[test]
+    fn mem_ap_address_sanity() {
+        assert_eq!(mem_ap_address(0x0, 0b00), 0x00);
+        assert_eq!(mem_ap_address(0x0, 0b01), 0x04);
+        assert_eq!(mem_ap_address(0x0, 0b11), 0x0C);
+        assert_eq!(mem_ap_address(0x1, 0b10), 0x18);
+        assert_eq!(mem_ap_address(0xF, 0b10), 0xF8);
+        assert_eq!(mem_ap_address(0xF, 0b11), 0xFC);
+    }
+
+    #[test]
+    fn select_write_interrupted_by_wait() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h04000000",
+                "DP WR A:2 ACK:2 Wait",
+                "DP WR A:2 ACK:1 OK Data:h04000010",
+            ],
+        );
+        assert_eq!(dp.selected_ap(), ApAddress::Apsel(4));
+        assert_eq!(dp.select().apbanksel(), 1);
+        assert_eq!(dp.ap_address(0), 0x10);
+
+        // A WAIT on the SELECT write leaves the previous selection in place
+        apply(&mut dp, &["DP WR A:2 ACK:2 Wait"]);
+        assert_eq!(dp.select().apbanksel(), 1);
+    }
+
+    #[test]
+    fn dpbanksel() {
+        let mut dp = DpState::new();
+        apply(&mut dp, &["DP WR A:2 ACK:1 OK Data:h01000012"]);
+        assert_eq!(dp.selected_ap(), ApAddress::Apsel(1));
+        assert_eq!(dp.select().apbanksel(), 1);
+        assert_eq!(dp.select().dpbanksel(), 2);
+        // CTRL/STAT is only accessible in DP bank 0
+        apply(&mut dp, &["DP RD A:1 ACK:1 OK Data:h00000022"]);
+        assert_eq!(dp.ctrl_stat(), None);
+    }
+
+    #[test]
+    fn dp_version() {
+        let mut dp = DpState::new();
+        assert_eq!(dp.version(), None);
+        apply(&mut dp, &["DP RD A:0 ACK:1 OK Data:h6BA02477"]);
+        assert_eq!(dp.version(), Some(DpVersion::V2));
+        apply(&mut dp, &["DP WR A:2 ACK:1 OK Data:h00000003"]);
+        assert_eq!(dp.dp_bank(), 3);
+
+        // DPv0 ignores SELECT[3:1]
+        apply(&mut dp, &["DP RD A:0 ACK:1 OK Data:h0BA00477"]);
+        assert_eq!(dp.version(), Some(DpVersion::V0));
+        assert_eq!(dp.dp_bank(), 1);
+    }
+
+    #[test]
+    fn power_up_sequence() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP RD A:0 ACK:1 OK Data:h6BA02477",
+                "DP WR A:0 ACK:1 OK Data:h0000001E",
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "DP WR A:1 ACK:1 OK Data:h50000000",
+                "DP RD A:1 ACK:1 OK Data:hF0000000",
+            ],
+        );
+        let ctrl_stat = dp.ctrl_stat().unwrap();
+        assert!(ctrl_stat.cdbgpwrupack());
+        assert!(ctrl_stat.csyspwrupack());
+        assert!(!dp.sticky_flags().any());
+    }
+
+    #[test]
+    fn sticky_flags_cleared_by_abort() {
+        let mut dp = DpState::new();
+        apply(&mut dp, &["AP RD A:3 ACK:4 Fault"]);
+        assert!(dp.sticky_flags().fault);
+        apply(&mut dp, &["DP RD A:1 ACK:1 OK Data:hF0000022"]);
+        let sticky = dp.sticky_flags();
+        assert!(sticky.stickyerr);
+        assert!(sticky.stickyorun);
+        assert!(!sticky.fault);
+        apply(&mut dp, &["DP WR A:0 ACK:1 OK Data:h00000004"]);
+        assert!(!dp.sticky_flags().stickyerr);
+        assert!(dp.sticky_flags().stickyorun);
+        apply(&mut dp, &["DP WR A:0 ACK:1 OK Data:h0000001E"]);
+        assert!(!dp.sticky_flags().any());
+    }
+
+    #[test]
+    fn posted_read_pipeline() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &["DP WR A:2 ACK:1 OK Data:h04000000", "AP RD A:3 ACK:2 Wait"],
+        );
+        assert_eq!(dp.posted_read(), None);
+        apply(&mut dp, &["AP RD A:3 ACK:1 OK Data:h00000000"]);
+        let drw_read = PostedRead {
+            ap: ApAddress::Apsel(4),
+            address: 0x0C,
+            memory_address: None,
+            csw: None,
+        };
+        assert_eq!(dp.posted_read(), Some(drw_read));
+        apply(&mut dp, &["DP RD A:3 ACK:1 OK Data:h40000001"]);
+        assert_eq!(dp.posted_read(), None);
+    }
+
+    #[test]
+    fn posted_read_results() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h04000000",
+                "AP WR A:0 ACK:1 OK Data:h03000002",
+                "AP WR A:1 ACK:1 OK Data:hE000ED78",
+            ],
+        );
+        let op = |s| parser::parse(s).unwrap().1;
+        let ed78_read = PostedRead {
+            ap: ApAddress::Apsel(4),
+            address: 0x0C,
+            memory_address: Some(0xE000_ED78),
+            csw: Some(Csw(0x0300_0002)),
+        };
+
+        // The first read returns stale data
+        assert_eq!(dp.update(&op("AP RD A:3 ACK:1 OK Data:h40000001")), None);
+        assert_eq!(dp.update(&op("AP RD A:3 ACK:2 Wait")), None);
+        assert_eq!(
+            dp.update(&op("AP RD A:3 ACK:1 OK Data:h09000003")),
+            Some(ReadResult {
+                read: ed78_read,
+                data: 0x0900_0003
+            })
+        );
+
+        // A TAR write doesn't disturb the pending read
+        assert_eq!(dp.update(&op("AP WR A:1 ACK:1 OK Data:hE000ED14")), None);
+        assert_eq!(
+            dp.update(&op("AP RD A:3 ACK:1 OK Data:h09000003")),
+            Some(ReadResult {
+                read: ed78_read,
+                data: 0x0900_0003
+            })
+        );
+        assert_eq!(
+            dp.update(&op("DP RD A:3 ACK:1 OK Data:h00040200")),
+            Some(ReadResult {
+                read: PostedRead {
+                    memory_address: Some(0xE000_ED14),
+                    ..ed78_read
+                },
+                data: 0x0004_0200
+            })
+        );
+        assert_eq!(dp.update(&op("DP RD A:3 ACK:1 OK Data:h00040200")), None);
+    }
+
+    #[test]
+    fn posted_tar_read_updates_ap_state() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h02000000",
+                "AP RD A:1 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h20000010",
+            ],
+        );
+        assert_eq!(
+            dp.ap(ApAddress::Apsel(2)).unwrap().tar,
+            Some(Tar(0x2000_0010))
+        );
+    }
+
+    #[test]
+    fn per_ap_state() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h04000000",
+                "AP WR A:0 ACK:1 OK Data:h03000002",
+                "AP WR A:1 ACK:1 OK Data:hE000EDF0",
+                "DP WR A:2 ACK:1 OK Data:h01000000",
+                "AP WR A:1 ACK:2 Wait",
+                "AP WR A:1 ACK:1 OK Data:h20000000",
+            ],
+        );
+        let ap4 = dp.ap(ApAddress::Apsel(4)).unwrap();
+        assert_eq!(ap4.csw, Some(Csw(0x0300_0002)));
+        assert_eq!(ap4.tar.map(|t| t.addr()), Some(0xE000_EDF0));
+        let ap1 = dp.ap(ApAddress::Apsel(1)).unwrap();
+        assert_eq!(ap1.csw, None);
+        assert_eq!(ap1.tar.map(|t| t.addr()), Some(0x2000_0000));
+    }
+
+    #[test]
+    fn ap_identity() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h040000F0",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h84770001",
+            ],
+        );
+        assert_eq!(
+            dp.ap(ApAddress::Apsel(4)).unwrap().idr,
+            Some(Idr(0x8477_0001))
+        );
+    }
+
+    #[test]
+    fn drw_auto_increment() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000052",
+                "AP WR A:1 ACK:1 OK Data:h200003F8",
+                "AP WR A:3 ACK:1 OK Data:h11111111",
+            ],
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_03FC)
+        );
+        // A WAIT doesn't perform the access
+        apply(&mut dp, &["AP WR A:3 ACK:2 Wait"]);
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_03FC)
+        );
+        // Wraps at the 1KB boundary
+        apply(&mut dp, &["AP WR A:3 ACK:1 OK Data:h22222222"]);
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_0000)
+        );
+
+        // Posted reads capture the address at issue
+        apply(
+            &mut dp,
+            &[
+                "AP WR A:1 ACK:1 OK Data:h20000100",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+            ],
+        );
+        assert_eq!(dp.posted_read().unwrap().memory_address, Some(0x2000_0104));
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_0108)
+        );
+    }
+
+    #[test]
+    fn drw_packed_and_halfword_increment() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000011",
+                "AP WR A:1 ACK:1 OK Data:h20000000",
+                "AP WR A:3 ACK:1 OK Data:h00001111",
+            ],
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_0002)
+        );
+        apply(
+            &mut dp,
+            &[
+                "AP WR A:0 ACK:1 OK Data:h23000020",
+                "AP WR A:3 ACK:1 OK Data:h44332211",
+            ],
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(0), Drw::ADDRESS.into()),
+            Some(0x2000_0006)
+        );
+    }
+
+    #[test]
+    fn drw_without_auto_increment() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h04000000",
+                "AP WR A:0 ACK:1 OK Data:h03000002",
+                "AP WR A:1 ACK:1 OK Data:hE000ED78",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+            ],
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(4), Drw::ADDRESS.into()),
+            Some(0xE000_ED78)
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(4), Bd3::ADDRESS.into()),
+            Some(0xE000_ED7C)
+        );
+    }
+
+    #[test]
+    fn unknown_csw_invalidates_tar() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h04000000",
+                "AP WR A:1 ACK:1 OK Data:hE000ED78",
+            ],
+        );
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(4), Drw::ADDRESS.into()),
+            Some(0xE000_ED78)
+        );
+        apply(&mut dp, &["AP WR A:3 ACK:1 OK Data:h00000000"]);
+        assert_eq!(
+            dp.memory_address(ApAddress::Apsel(4), Drw::ADDRESS.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn line_and_protocol_reset() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP RD A:0 ACK:1 OK Data:h6BA02477",
+                "DP WR A:2 ACK:1 OK Data:h04000012",
+                "AP RD A:0 ACK:1 OK Data:h00000000",
+                "AP RD A:3 ACK:4 Fault",
+            ],
+        );
+        assert_eq!(dp.dp_bank(), 2);
+        dp.line_reset();
+        assert_eq!(dp.posted_read(), None);
+        assert_eq!(dp.dp_bank(), 0);
+        // APSEL and the sticky flags aren't reset by a line reset
+        assert_eq!(dp.selected_ap(), ApAddress::Apsel(4));
+        assert!(dp.sticky_flags().fault);
+
+        dp.protocol_reset();
+        assert_eq!(dp.select(), Select(0));
+        assert!(!dp.sticky_flags().any());
+        assert_eq!(dp.version(), Some(DpVersion::V2));
+    }
+
+    #[test]
+    fn adiv6_ap_addressing() {
+        let mut dp = DpState::new();
+        apply(
+            &mut dp,
+            &[
+                "DP RD A:0 ACK:1 OK Data:h6BA03477",
+                "DP WR A:2 ACK:1 OK Data:h000D0D00",
+                "AP WR A:0 ACK:1 OK Data:h23000052",
+                "AP WR A:1 ACK:1 OK Data:h20000000",
+            ],
+        );
+        assert!(dp.is_adiv6());
+        let ap = ApAddress::Base(0x000D_0000);
+        assert_eq!(dp.selected_ap(), ap);
+        assert_eq!(dp.ap_address(3), 0xD0C);
+        assert_eq!(dp.memory_address(ap, 0xD0C), Some(0x2000_0000));
+        assert_eq!(dp.ap(ap).unwrap().csw, Some(Csw(0x2300_0052)));
+
+        // IDR at 0xDFC
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h000D0DF0",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h34770008",
+            ],
+        );
+        assert_eq!(dp.ap(ap).unwrap().idr, Some(Idr(0x3477_0008)));
+
+        // SELECT1 extends the base address, the banked 0x00 registers
+        // don't replace the DPIDR
+        apply(
+            &mut dp,
+            &[
+                "DP WR A:2 ACK:1 OK Data:h000D0005",
+                "DP WR A:1 ACK:1 OK Data:h00000001",
+                "DP WR A:2 ACK:1 OK Data:h00002D02",
+                "DP RD A:0 ACK:1 OK Data:h000D0001",
+            ],
+        );
+        assert_eq!(dp.selected_ap(), ApAddress::Base(0x1_0000_2000));
+        assert_eq!(dp.ap_address(0), 0xD00);
+        assert_eq!(dp.dpidr(), Some(DpIdr(0x6BA0_3477)));
+    }
+
+    #[test]
+    fn adiv6_forced() {
+        let mut dp = DpState::adiv6();
+        assert!(dp.is_adiv6());
+        apply(&mut dp, &["DP WR A:2 ACK:1 OK Data:h00080DF0"]);
+        assert_eq!(dp.selected_ap(), ApAddress::Base(0x0008_0000));
+        assert_eq!(ApAddress::Base(0x0008_0000).register(0xDFC), Some(0xFC));
+        assert_eq!(ApAddress::Base(0x0008_0000).register(0xFFC), None);
+        assert_eq!(ApAddress::Base(0x0008_0000).to_string(), "@00080000");
+        assert_eq!(ApAddress::Apsel(4).to_string(), "04");
+    }
+}
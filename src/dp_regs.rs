@@ -0,0 +1,448 @@
+use crate::jep106::Jep106Code;
+use bitfield::bitfield;
+use std::fmt;
+
+bitfield! {
+    /// DP Identification Register, DPIDR (IDCODE), see ADIv5.2 B2.2.5
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct DpIdr(u32);
+    /// Read-as-one
+    pub rao, _ : 0;
+    /// JEP106 code of the DP designer, see [`DpIdr::designer_code`]
+    pub u16, designer, _ : 11, 1;
+    /// DP architecture version, see [`DpIdr::dp_version`]
+    pub u8, version, _ : 15, 12;
+    /// Minimal DP, no pushed operations or transaction counter
+    pub mindp, _ : 16;
+    pub partno, _ : 27, 20;
+    pub revision, _ : 31, 28;
+}
+
+impl DpIdr {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "DPIDR";
+
+    pub fn designer_code(&self) -> Jep106Code {
+        Jep106Code::from_designer(self.designer())
+    }
+
+    pub fn dp_version(&self) -> DpVersion {
+        match self.version() {
+            0 => DpVersion::V0,
+            1 => DpVersion::V1,
+            2 => DpVersion::V2,
+            3 => DpVersion::V3,
+            version => DpVersion::Unknown(version),
+        }
+    }
+}
+
+impl fmt::Display for DpIdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "REVISION:{} PARTNO:{:02X} MIN:{} VERSION:{} DESIGNER:{}",
+            self.revision(),
+            self.partno(),
+            self.mindp() as u8,
+            self.dp_version(),
+            self.designer_code(),
+        )
+    }
+}
+
+/// DP architecture version
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum DpVersion {
+    /// JTAG-DP only, SELECT[0] is CTRLSEL
+    V0,
+    /// SELECT[3:0] is DPBANKSEL
+    V1,
+    /// Adds TARGETID, DLPIDR, EVENTSTAT and TARGETSEL (multi-drop)
+    V2,
+    /// ADIv6, adds SELECT1 and the AP address space
+    V3,
+    Unknown(u8),
+}
+
+impl fmt::Display for DpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DpVersion::V0 => f.write_str("DPv0"),
+            DpVersion::V1 => f.write_str("DPv1"),
+            DpVersion::V2 => f.write_str("DPv2"),
+            DpVersion::V3 => f.write_str("DPv3"),
+            DpVersion::Unknown(v) => write!(f, "DPv?({})", v),
+        }
+    }
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Abort(u32);
+    pub dapabort, _ : 0;
+    pub stkcmpclr, _ : 1;
+    pub stkerrclr, _ : 2;
+    pub wderrclr, _ : 3;
+    pub orunerrclr, _ : 4;
+}
+
+impl Abort {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "ABORT";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct CtrlStat(u32);
+    /// Overrun detection enable
+    pub orundetect, _ : 0;
+    /// Sticky overrun error flag
+    pub stickyorun, _ : 1;
+    pub trnmode, _ : 3, 2;
+    /// Sticky compare flag, pushed compare/verify mismatch
+    pub stickycmp, _ : 4;
+    /// Sticky error flag, an AP transaction failed
+    pub stickyerr, _ : 5;
+    pub readok, _ : 6;
+    pub wdataerr, _ : 7;
+    pub trncnt, _ : 21, 12;
+    /// Debug reset request
+    pub cdbgrstreq, _ : 26;
+    /// Debug reset acknowledge
+    pub cdbgrstack, _ : 27;
+    /// Debug power-up request
+    pub cdbgpwrupreq, _ : 28;
+    /// Debug power-up acknowledge
+    pub cdbgpwrupack, _ : 29;
+    /// System power-up request
+    pub csyspwrupreq, _ : 30;
+    /// System power-up acknowledge
+    pub csyspwrupack, _ : 31;
+}
+
+impl CtrlStat {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "CTRL/STAT";
+    pub const BANK: u8 = 0;
+}
+
+bitfield! {
+    /// Wire Control Register, WCR (CTRLSEL/DPBANKSEL 1) of ADIv5.0/5.1 SW-DPs
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Wcr(u32);
+    pub prescaler, _ : 2, 0;
+    pub wiremode, _ : 7, 6;
+    pub turnround, _ : 9, 8;
+}
+
+impl Wcr {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "WCR";
+    pub const BANK: u8 = 1;
+}
+
+bitfield! {
+    /// Data Link Control Register, DLCR (DPBANKSEL 1), see ADIv5.2 B2.2.4
+    ///
+    /// Replaces WCR from DPv1, only TURNROUND remains configurable
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Dlcr(u32);
+    /// Turnaround tristate period, in data periods minus one
+    pub turnround, _ : 9, 8;
+}
+
+impl Dlcr {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "DLCR";
+    pub const BANK: u8 = 1;
+}
+
+bitfield! {
+    /// Target Identification register, TARGETID (DPBANKSEL 2), see ADIv5.2 B2.2.10
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct TargetId(u32);
+    /// Read-as-one
+    pub rao, _ : 0;
+    /// JEP106 code of the target designer, see [`TargetId::designer_code`]
+    pub u16, tdesigner, _ : 11, 1;
+    pub u16, tpartno, _ : 27, 12;
+    pub trevision, _ : 31, 28;
+}
+
+impl TargetId {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "TARGETID";
+    pub const BANK: u8 = 2;
+
+    pub fn designer_code(&self) -> Jep106Code {
+        Jep106Code::from_designer(self.tdesigner())
+    }
+}
+
+impl fmt::Display for TargetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TREVISION:{} TPARTNO:{:04X} TDESIGNER:{}",
+            self.trevision(),
+            self.tpartno(),
+            self.designer_code(),
+        )
+    }
+}
+
+bitfield! {
+    /// Data Link Protocol Identification Register, DLPIDR (DPBANKSEL 3), see ADIv5.2 B2.2.5
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Dlpidr(u32);
+    /// Protocol version, 1 is SWD protocol version 2
+    pub protvsn, _ : 3, 0;
+    /// Instance number of this DP in a multi-drop system
+    pub tinstance, _ : 31, 28;
+}
+
+impl Dlpidr {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "DLPIDR";
+    pub const BANK: u8 = 3;
+}
+
+bitfield! {
+    /// Event Status register, EVENTSTAT (DPBANKSEL 4), see ADIv5.2 B2.2.6
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct EventStat(u32);
+    /// Event status flag, clear when an event requires attention
+    pub ea, _ : 0;
+}
+
+impl EventStat {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "EVENTSTAT";
+    pub const BANK: u8 = 4;
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Select(u32);
+    /// DPv0 CTRL/STAT register select
+    pub ctrlsel, _ : 0;
+    /// DPv1+ DP register bank select, replaces CTRLSEL
+    pub dpbanksel, set_dpbanksel : 3, 0;
+    pub apbanksel, _ : 7, 4;
+    pub apsel, _ : 31, 24;
+    /// ADIv6 AP address bits 31:4, replaces APSEL and APBANKSEL
+    pub addr, _ : 31, 4;
+}
+
+impl Select {
+    pub const ADDRESS: u8 = 0x08;
+    pub const NAME: &'static str = "SELECT";
+}
+
+impl Select {
+    /// ADIv6 AP address bits 31:0, with A[3:2] clear
+    pub fn adiv6_address(&self) -> u32 {
+        self.0 & !0xF
+    }
+}
+
+bitfield! {
+    /// AP Select register 1, SELECT1 (DPBANKSEL 5), see ADIv6 B2.2.12
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Select1(u32);
+    /// AP address bits 63:32
+    pub addr, _ : 31, 0;
+}
+
+impl Select1 {
+    pub const ADDRESS: u8 = 0x04;
+    pub const NAME: &'static str = "SELECT1";
+    pub const BANK: u8 = 5;
+}
+
+bitfield! {
+    /// DP Identification Register 1, DPIDR1 (DPBANKSEL 1), see ADIv6 B2.2.7
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct DpIdr1(u32);
+    /// Address size in bits, 32 or 64
+    pub asize, _ : 6, 0;
+    /// Error mode, errors are reported per AP access rather than sticky
+    pub errmode, _ : 7;
+}
+
+impl DpIdr1 {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "DPIDR1";
+    pub const BANK: u8 = 1;
+}
+
+bitfield! {
+    /// Base Pointer register 0, BASEPTR0 (DPBANKSEL 2), see ADIv6 B2.2.1
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct BasePtr0(u32);
+    /// PTR holds the address of the top-level component
+    pub valid, _ : 0;
+    /// Base address bits 31:12
+    pub ptr, _ : 31, 12;
+}
+
+impl BasePtr0 {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "BASEPTR0";
+    pub const BANK: u8 = 2;
+
+    /// Base address bits 31:0
+    pub fn address(&self) -> u32 {
+        self.0 & !0xFFF
+    }
+}
+
+bitfield! {
+    /// Base Pointer register 1, BASEPTR1 (DPBANKSEL 3), see ADIv6 B2.2.2
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct BasePtr1(u32);
+    /// Base address bits 63:32
+    pub ptr, _ : 31, 0;
+}
+
+impl BasePtr1 {
+    pub const ADDRESS: u8 = 0x00;
+    pub const NAME: &'static str = "BASEPTR1";
+    pub const BANK: u8 = 3;
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct Resend(u32);
+}
+
+impl Resend {
+    pub const ADDRESS: u8 = 0x08;
+    pub const NAME: &'static str = "RESEND";
+}
+
+bitfield! {
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct RdBuff(u32);
+}
+
+impl RdBuff {
+    pub const ADDRESS: u8 = 0x0C;
+    pub const NAME: &'static str = "RDBUFF";
+}
+
+bitfield! {
+    /// Target Selection register, TARGETSEL, see ADIv5.2 B2.2.11
+    ///
+    /// Written immediately after a line reset to select one target of a
+    /// multi-drop system, the targets don't drive the ACK of this write
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub struct TargetSel(u32);
+    /// Should-be-one
+    pub sbo, _ : 0;
+    /// JEP106 code of the target designer, see [`TargetSel::designer_code`]
+    pub u16, tdesigner, _ : 11, 1;
+    pub u16, tpartno, _ : 27, 12;
+    /// Instance number of the target DP, see [`Dlpidr::tinstance`]
+    pub tinstance, _ : 31, 28;
+}
+
+impl TargetSel {
+    pub const ADDRESS: u8 = 0x0C;
+    pub const NAME: &'static str = "TARGETSEL";
+
+    pub fn designer_code(&self) -> Jep106Code {
+        Jep106Code::from_designer(self.tdesigner())
+    }
+}
+
+impl fmt::Display for TargetSel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TINSTANCE:{} TPARTNO:{:04X} TDESIGNER:{}",
+            self.tinstance(),
+            self.tpartno(),
+            self.designer_code(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dpidr_decode() {
+        // Cortex-M7 SW-DP
+        let dpidr = DpIdr(0x6BA0_2477);
+        assert!(dpidr.rao());
+        assert_eq!(dpidr.designer_code(), Jep106Code::ARM);
+        assert_eq!(dpidr.dp_version(), DpVersion::V2);
+        assert!(!dpidr.mindp());
+        assert_eq!(dpidr.partno(), 0xBA);
+        assert_eq!(dpidr.revision(), 6);
+        assert_eq!(
+            dpidr.to_string(),
+            "REVISION:6 PARTNO:BA MIN:0 VERSION:DPv2 DESIGNER:ARM Ltd (4:3B)"
+        );
+
+        // Cortex-M0+ MINDP
+        let dpidr = DpIdr(0x0BC1_2477);
+        assert!(dpidr.mindp());
+        assert_eq!(dpidr.partno(), 0xBC);
+        assert_eq!(dpidr.dp_version(), DpVersion::V2);
+
+        // Cortex-M3 SW-DP
+        assert_eq!(DpIdr(0x2BA0_1477).dp_version(), DpVersion::V1);
+    }
+
+    #[test]
+    fn dpv2_bank_registers() {
+        // RP2040 core 0
+        let targetid = TargetId(0x0100_2927);
+        assert!(targetid.rao());
+        assert_eq!(targetid.designer_code(), Jep106Code::new(9, 0x13));
+        assert_eq!(targetid.tpartno(), 0x1002);
+        assert_eq!(targetid.trevision(), 0);
+        assert_eq!(
+            targetid.to_string(),
+            "TREVISION:0 TPARTNO:1002 TDESIGNER:Raspberry Pi Trading (9:13)"
+        );
+
+        let dlpidr = Dlpidr(0x1000_0001);
+        assert_eq!(dlpidr.protvsn(), 1);
+        assert_eq!(dlpidr.tinstance(), 1);
+
+        assert_eq!(Dlcr(0x0000_0140).turnround(), 1);
+        assert!(!EventStat(0).ea());
+    }
+
+    #[test]
+    fn targetsel_decode() {
+        // RP2040 core 1
+        let targetsel = TargetSel(0x1100_2927);
+        assert!(targetsel.sbo());
+        assert_eq!(targetsel.designer_code(), Jep106Code::new(9, 0x13));
+        assert_eq!(targetsel.tpartno(), 0x1002);
+        assert_eq!(targetsel.tinstance(), 1);
+        assert_eq!(
+            targetsel.to_string(),
+            "TINSTANCE:1 TPARTNO:1002 TDESIGNER:Raspberry Pi Trading (9:13)"
+        );
+    }
+
+    #[test]
+    fn adiv6_select() {
+        let select = Select(0x000D_0F03);
+        assert_eq!(select.dpbanksel(), 3);
+        assert_eq!(select.addr(), 0x0000_D0F0);
+        assert_eq!(select.adiv6_address(), 0x000D_0F00);
+
+        let baseptr0 = BasePtr0(0x000D_0001);
+        assert!(baseptr0.valid());
+        assert_eq!(baseptr0.address(), 0x000D_0000);
+        assert_eq!(DpIdr1(0x20).asize(), 32);
+    }
+}
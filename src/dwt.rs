@@ -0,0 +1,204 @@
+//! Watchpoint and cycle counter configuration through the Data Watchpoint and
+//! Trace unit
+
+use crate::arm_regs::{ArchVersion, ArmRegister, ComparatorFunction, ComparatorMatch};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A change to the DWT configuration made by the debugger
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DwtEvent {
+    /// DWT_FUNCTIONn was written with a function
+    Configured {
+        comparator: u8,
+        function: ComparatorFunction,
+        /// DWT_COMPn, if seen
+        comp: Option<u32>,
+        /// DWT_MASKn, if seen, Armv7-M only
+        mask: Option<u8>,
+    },
+    /// DWT_FUNCTIONn was written disabling the comparator
+    Disabled { comparator: u8 },
+    /// DWT_CTRL.CYCCNTENA was changed
+    CycleCounter { enabled: bool },
+}
+
+impl fmt::Display for DwtEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DwtEvent::Configured {
+                comparator,
+                function,
+                comp,
+                mask,
+            } => {
+                write!(f, "DWT comparator {}: {}", comparator, function)?;
+                match (function.on, comp) {
+                    (ComparatorMatch::CycleCount, Some(comp)) => write!(f, " {}", comp)?,
+                    (_, Some(comp)) => write!(f, " @{:08X}", comp)?,
+                    (_, None) => f.write_str(" @?")?,
+                }
+                match mask {
+                    Some(mask) if *mask != 0 => write!(f, " mask:{}", mask),
+                    _ => Ok(()),
+                }
+            }
+            DwtEvent::Disabled { comparator } => {
+                write!(f, "DWT comparator {} disabled", comparator)
+            }
+            DwtEvent::CycleCounter { enabled: true } => f.write_str("cycle counter enabled"),
+            DwtEvent::CycleCounter { enabled: false } => f.write_str("cycle counter disabled"),
+        }
+    }
+}
+
+/// DWT state of one MEM-AP
+#[derive(Clone, Debug, Default)]
+struct Dwt {
+    /// Known once CPUID or an Armv8-M DWT_FUNCTIONn was read
+    arch: Option<ArchVersion>,
+    comps: BTreeMap<u8, u32>,
+    masks: BTreeMap<u8, u8>,
+    cyccntena: Option<bool>,
+}
+
+/// Tracks DWT register accesses per MEM-AP. DWT_FUNCTIONn is taken in the
+/// Armv7-M layout unless CPUID or an Armv8-M DWT_FUNCTIONn was read
+#[derive(Clone, Debug, Default)]
+pub struct DwtTracker {
+    dwts: BTreeMap<ApAddress, Dwt>,
+}
+
+impl DwtTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the configuration change it made, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<DwtEvent> {
+        let dwt = self.dwts.entry(ap).or_default();
+        match (access.register?, direction) {
+            (ArmRegister::DwtCtrl(ctrl), direction) => {
+                let enabled = ctrl.cyccntena();
+                let changed = dwt.cyccntena.replace(enabled) != Some(enabled);
+                (direction == Direction::Write && changed)
+                    .then_some(DwtEvent::CycleCounter { enabled })
+            }
+            (ArmRegister::Cpuid(cpuid), Direction::Read) => {
+                dwt.arch = cpuid.arch_version().or(dwt.arch);
+                None
+            }
+            (ArmRegister::DwtComp(n, comp), _) => {
+                dwt.comps.insert(n, comp.0);
+                None
+            }
+            (ArmRegister::DwtMask(n, mask), _) => {
+                dwt.masks.insert(n, mask.mask());
+                None
+            }
+            (ArmRegister::DwtFunction(_, function), Direction::Read) => {
+                if function.guess_arch() == ArchVersion::Armv8M {
+                    dwt.arch = Some(ArchVersion::Armv8M);
+                }
+                None
+            }
+            (ArmRegister::DwtFunction(comparator, function), Direction::Write) => {
+                let arch = dwt.arch.unwrap_or(ArchVersion::Armv7M);
+                Some(match function.comparator_function(arch) {
+                    Some(function) => DwtEvent::Configured {
+                        comparator,
+                        function,
+                        comp: dwt.comps.get(&comparator).copied(),
+                        mask: dwt.masks.get(&comparator).copied(),
+                    },
+                    None => DwtEvent::Disabled { comparator },
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arm_regs::{ComparatorAction, DataAccess};
+    use crate::test_util::track;
+
+    #[test]
+    fn armv7m_watchpoint() {
+        let mut tracker = DwtTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000012",
+                // DWT_CTRL, enabling CYCCNT
+                "AP WR A:1 ACK:1 OK Data:hE0001000",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h40000000",
+                "AP WR A:1 ACK:1 OK Data:hE0001000",
+                "AP WR A:3 ACK:1 OK Data:h40000001",
+                // DWT_COMP1, DWT_MASK1, DWT_FUNCTION1
+                "AP WR A:1 ACK:1 OK Data:hE0001030",
+                "AP WR A:3 ACK:1 OK Data:h20000100",
+                "AP WR A:3 ACK:1 OK Data:h00000002",
+                "AP WR A:3 ACK:1 OK Data:h00000806",
+                "AP WR A:1 ACK:1 OK Data:hE0001038",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], DwtEvent::CycleCounter { enabled: true });
+        assert_eq!(
+            events[1],
+            DwtEvent::Configured {
+                comparator: 1,
+                function: ComparatorFunction {
+                    action: ComparatorAction::Watchpoint,
+                    on: ComparatorMatch::DataAddress(DataAccess::Write),
+                },
+                comp: Some(0x2000_0100),
+                mask: Some(2),
+            }
+        );
+        assert_eq!(
+            events[1].to_string(),
+            "DWT comparator 1: watchpoint on data write @20000100 mask:2"
+        );
+        assert_eq!(events[2], DwtEvent::Disabled { comparator: 1 });
+    }
+
+    #[test]
+    fn armv8m_function() {
+        let mut tracker = DwtTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000002",
+                // DWT_FUNCTION0 reads with an ID
+                "AP WR A:1 ACK:1 OK Data:hE0001028",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h58000000",
+                "AP WR A:1 ACK:1 OK Data:hE0001020",
+                "AP WR A:3 ACK:1 OK Data:h08000400",
+                // ACTION debug event, MATCH instruction address
+                "AP WR A:1 ACK:1 OK Data:hE0001028",
+                "AP WR A:3 ACK:1 OK Data:h00000012",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(
+            events.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["DWT comparator 0: watchpoint on instruction address @08000400"]
+        );
+    }
+}
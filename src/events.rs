@@ -0,0 +1,74 @@
+//! Debug events derived from memory accesses to the debug and system registers
+
+use crate::core_regs::{CoreRegisterAccess, CoreRegisterTracker};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::dwt::{DwtEvent, DwtTracker};
+use crate::fpb::{BreakpointEvent, BreakpointTracker};
+use crate::mpu::{MpuEvent, MpuTracker};
+use crate::parser::Direction;
+use crate::swo::{SwoEvent, SwoTracker};
+use std::fmt;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DebugEvent {
+    CoreRegister(CoreRegisterAccess),
+    Breakpoint(BreakpointEvent),
+    Dwt(DwtEvent),
+    Swo(SwoEvent),
+    Mpu(MpuEvent),
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugEvent::CoreRegister(e) => e.fmt(f),
+            DebugEvent::Breakpoint(e) => e.fmt(f),
+            DebugEvent::Dwt(e) => e.fmt(f),
+            DebugEvent::Swo(e) => e.fmt(f),
+            DebugEvent::Mpu(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Feeds memory accesses to every debug event tracker
+#[derive(Clone, Debug, Default)]
+pub struct DebugEventTracker {
+    core_registers: CoreRegisterTracker,
+    breakpoints: BreakpointTracker,
+    dwt: DwtTracker,
+    swo: SwoTracker,
+    mpu: MpuTracker,
+}
+
+impl DebugEventTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the event it caused, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<DebugEvent> {
+        let core_register = self
+            .core_registers
+            .push(ap, direction, access)
+            .map(DebugEvent::CoreRegister);
+        let breakpoint = self
+            .breakpoints
+            .push(ap, direction, access)
+            .map(DebugEvent::Breakpoint);
+        let dwt = self.dwt.push(ap, direction, access).map(DebugEvent::Dwt);
+        let swo = self.swo.push(ap, direction, access).map(DebugEvent::Swo);
+        let mpu = self.mpu.push(ap, direction, access).map(DebugEvent::Mpu);
+        core_register.or(breakpoint).or(dwt).or(swo).or(mpu)
+    }
+
+    /// The MPU and SAU configuration as known now
+    pub fn mpu(&self) -> &MpuTracker {
+        &self.mpu
+    }
+}
@@ -0,0 +1,250 @@
+//! Export of a reconstructed [`MemoryImage`](crate::image::MemoryImage) to
+//! common image file formats
+
+use crate::image::Region;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Data bytes per Intel HEX and S-record data record
+const RECORD_BYTES: usize = 16;
+
+/// Intel HEX, with extended linear address records for addresses above 64 KiB
+pub fn write_ihex(regions: &[Region], mut w: impl Write) -> io::Result<()> {
+    let mut upper = 0u16;
+    for region in regions {
+        // Aligned records never cross a 64 KiB segment
+        for (address, data) in records(region) {
+            if (address >> 16) as u16 != upper {
+                upper = (address >> 16) as u16;
+                ihex_record(&mut w, 0, 0x04, &upper.to_be_bytes())?;
+            }
+            ihex_record(&mut w, address as u16, 0x00, data)?;
+        }
+    }
+    ihex_record(&mut w, 0, 0x01, &[])
+}
+
+fn ihex_record(w: &mut impl Write, address: u16, kind: u8, data: &[u8]) -> io::Result<()> {
+    let mut record = vec![data.len() as u8];
+    record.extend_from_slice(&address.to_be_bytes());
+    record.push(kind);
+    record.extend_from_slice(data);
+    let checksum = record
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_add(*b))
+        .wrapping_neg();
+    write!(w, ":")?;
+    for b in record.iter() {
+        write!(w, "{:02X}", b)?;
+    }
+    writeln!(w, "{:02X}", checksum)
+}
+
+/// Motorola S-record with 32-bit addresses (S3 data, S7 termination)
+pub fn write_srec(regions: &[Region], mut w: impl Write) -> io::Result<()> {
+    srec_record(&mut w, 0, &[0, 0], b"swd")?;
+    let mut count = 0usize;
+    for region in regions {
+        for (address, chunk) in records(region) {
+            srec_record(&mut w, 3, &address.to_be_bytes(), chunk)?;
+            count += 1;
+        }
+    }
+    if count <= 0xFFFF {
+        srec_record(&mut w, 5, &(count as u16).to_be_bytes(), &[])?;
+    }
+    srec_record(&mut w, 7, &[0; 4], &[])
+}
+
+fn srec_record(w: &mut impl Write, kind: u8, address: &[u8], data: &[u8]) -> io::Result<()> {
+    let mut record = vec![(address.len() + data.len() + 1) as u8];
+    record.extend_from_slice(address);
+    record.extend_from_slice(data);
+    let checksum = !record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    write!(w, "S{}", kind)?;
+    for b in record.iter() {
+        write!(w, "{:02X}", b)?;
+    }
+    writeln!(w, "{:02X}", checksum)
+}
+
+/// Split a region into data records, aligned to the record size
+fn records(region: &Region) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut address = region.address;
+    let mut data = region.data.as_slice();
+    std::iter::from_fn(move || {
+        if data.is_empty() {
+            return None;
+        }
+        let len = (RECORD_BYTES - address as usize % RECORD_BYTES).min(data.len());
+        let (chunk, rest) = data.split_at(len);
+        let record = (address, chunk);
+        address = address.wrapping_add(len as u32);
+        data = rest;
+        Some(record)
+    })
+}
+
+/// One raw binary file per region in a directory, named by the region's
+/// start address. Returns the paths written
+pub fn write_bin(regions: &[Region], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    regions
+        .iter()
+        .map(|region| {
+            let path = dir.join(format!("{:08X}.bin", region.address));
+            fs::write(&path, &region.data)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+const ELF_HEADER_SIZE: u16 = 52;
+const PROGRAM_HEADER_SIZE: u16 = 32;
+const EM_ARM: u16 = 40;
+const ET_EXEC: u16 = 2;
+const PT_LOAD: u32 = 1;
+const PF_R: u32 = 0x4;
+const PF_W: u32 = 0x2;
+/// EABI version 5
+const EF_ARM_EABI_VER5: u32 = 0x0500_0000;
+
+/// ELF32 little-endian ARM executable with one loadable segment per region
+/// and no sections
+pub fn write_elf(regions: &[Region], mut w: impl Write) -> io::Result<()> {
+    let phnum = u16::try_from(regions.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many regions for ELF"))?;
+    let phoff = u32::from(ELF_HEADER_SIZE);
+
+    let mut header = Vec::with_capacity(usize::from(ELF_HEADER_SIZE));
+    header.extend_from_slice(b"\x7FELF");
+    // ELFCLASS32, ELFDATA2LSB, EV_CURRENT, ELFOSABI_NONE
+    header.extend_from_slice(&[1, 1, 1, 0]);
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&ET_EXEC.to_le_bytes());
+    header.extend_from_slice(&EM_ARM.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes()); // e_version
+    header.extend_from_slice(&0u32.to_le_bytes()); // e_entry
+    header.extend_from_slice(&phoff.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes()); // e_shoff
+    header.extend_from_slice(&EF_ARM_EABI_VER5.to_le_bytes());
+    header.extend_from_slice(&ELF_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&PROGRAM_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&phnum.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shentsize
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shnum
+    header.extend_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+    w.write_all(&header)?;
+
+    let mut offset = phoff + u32::from(PROGRAM_HEADER_SIZE) * u32::from(phnum);
+    for region in regions {
+        let size = region.data.len() as u32;
+        let mut flags = PF_R;
+        if region.written {
+            flags |= PF_W;
+        }
+        for field in [
+            PT_LOAD,
+            offset,
+            region.address, // p_vaddr
+            region.address, // p_paddr
+            size,           // p_filesz
+            size,           // p_memsz
+            flags,
+            1, // p_align
+        ] {
+            w.write_all(&field.to_le_bytes())?;
+        }
+        offset += size;
+    }
+
+    for region in regions {
+        w.write_all(&region.data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(address: u32, data: &[u8]) -> Region {
+        Region {
+            address,
+            data: data.to_vec(),
+            written: true,
+            read: false,
+        }
+    }
+
+    #[test]
+    fn ihex() {
+        let regions = [
+            region(0x0000_FFFC, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]),
+            region(0x2000_0000, &[0xAA]),
+        ];
+        let mut out = Vec::new();
+        write_ihex(&regions, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ":04FFFC0001020304F7\n\
+             :020000040001F9\n\
+             :020000000506F3\n\
+             :020000042000DA\n\
+             :01000000AA55\n\
+             :00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn srec() {
+        let regions = [region(0x2000_0000, &[0x11, 0x22, 0x33, 0x44])];
+        let mut out = Vec::new();
+        write_srec(&regions, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "S0060000737764AB\n\
+             S30920000000112233442C\n\
+             S5030001FB\n\
+             S70500000000FA\n"
+        );
+    }
+
+    #[test]
+    fn record_alignment() {
+        let data: Vec<u8> = (0..40).collect();
+        let region = region(0x2000_0008, &data);
+        let lens: Vec<_> = records(&region).map(|(a, d)| (a, d.len())).collect();
+        assert_eq!(
+            lens,
+            [(0x2000_0008, 8), (0x2000_0010, 16), (0x2000_0020, 16)]
+        );
+    }
+
+    #[test]
+    fn elf() {
+        let regions = [
+            region(0x2000_0000, &[1, 2, 3, 4]),
+            region(0x0800_0000, &[5, 6]),
+        ];
+        let mut out = Vec::new();
+        write_elf(&regions, &mut out).unwrap();
+        assert_eq!(out.len(), 52 + 2 * 32 + 6);
+        assert_eq!(&out[..4], b"\x7FELF");
+        assert_eq!(u16::from_le_bytes([out[18], out[19]]), EM_ARM);
+        assert_eq!(u16::from_le_bytes([out[44], out[45]]), 2);
+
+        let phdr = |i: usize, field: usize| {
+            let at = 52 + i * 32 + field * 4;
+            u32::from_le_bytes(out[at..at + 4].try_into().unwrap())
+        };
+        assert_eq!(phdr(0, 0), PT_LOAD);
+        assert_eq!(phdr(0, 1), 116);
+        assert_eq!(phdr(0, 3), 0x2000_0000);
+        assert_eq!(phdr(0, 4), 4);
+        assert_eq!(phdr(1, 1), 120);
+        assert_eq!(phdr(1, 2), 0x0800_0000);
+        assert_eq!(&out[116..], [1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,301 @@
+//! Detection of flash algorithm calls: the debugger downloads an algorithm
+//! into RAM, sets up PC, SP, LR and the R0-R3 arguments through DCRSR/DCRDR,
+//! resumes the core through DHCSR and polls DHCSR until the core halts on the
+//! breakpoint at the return address
+
+use crate::arm_regs::{ArmRegister, CoreRegister, DhcsrWrite};
+use crate::core_regs::CoreRegisterAccess;
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::image::{MemoryImage, Region};
+use crate::parser::{Direction, Timestamp};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Addresses from here are the System region, never RAM holding an algorithm
+const SYSTEM_REGION: u32 = 0xE000_0000;
+
+/// Position of an access in the log
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct LogPosition {
+    /// One-based line number
+    pub line: usize,
+    /// Timestamp of the capture block holding the line
+    pub timestamp: Option<Timestamp>,
+}
+
+/// One execution of a downloaded algorithm function
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AlgorithmCall {
+    pub ap: ApAddress,
+    /// The DHCSR write resuming the core
+    pub resumed: LogPosition,
+    pub entry: u32,
+    /// R0-R3, if written for the call
+    pub args: [Option<u32>; 4],
+    pub sp: Option<u32>,
+    /// LR, the algorithm returns to a breakpoint there
+    pub return_address: Option<u32>,
+    /// The RAM written by the debugger that holds the entry point
+    pub blob: Option<Region>,
+    pub halt: Option<AlgorithmHalt>,
+    /// R0 as read back after the halt
+    pub result: Option<u32>,
+}
+
+/// The core was seen halted again after an algorithm call
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct AlgorithmHalt {
+    /// The DHCSR read reporting S_HALT
+    pub position: LogPosition,
+    /// Number of DHCSR reads since the core was resumed, including the one
+    /// reporting S_HALT
+    pub polls: usize,
+}
+
+impl AlgorithmCall {
+    /// Time from resuming the core until the halt was observed, at the
+    /// resolution of the capture block timestamps
+    pub fn duration(&self) -> Option<Duration> {
+        let started = self.resumed.timestamp?;
+        let halted = self.halt?.position.timestamp?;
+        Some(halted.duration_since(started))
+    }
+}
+
+impl fmt::Display for AlgorithmCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: AP:{} call {:08X}(",
+            self.resumed.line, self.ap, self.entry
+        )?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match arg {
+                Some(arg) => write!(f, "{:08X}", arg)?,
+                None => f.write_str("?")?,
+            }
+        }
+        f.write_str(")")?;
+        if let Some(sp) = self.sp {
+            write!(f, " SP={:08X}", sp)?;
+        }
+        if let Some(lr) = self.return_address {
+            write!(f, " LR={:08X}", lr)?;
+        }
+        match self.halt {
+            Some(halt) => {
+                write!(f, " -> halted at line {}", halt.position.line)?;
+                if let Some(duration) = self.duration() {
+                    write!(f, " after {} ms", duration.as_millis())?;
+                }
+                write!(f, " ({} polls)", halt.polls)?;
+            }
+            None => f.write_str(" -> no halt observed")?,
+        }
+        if let Some(result) = self.result {
+            write!(f, " R0={:08X}", result)?;
+        }
+        Ok(())
+    }
+}
+
+/// Core state of one MEM-AP
+#[derive(Clone, Debug, Default)]
+struct Core {
+    /// Core registers written since the core was last resumed
+    registers: BTreeMap<CoreRegister, u32>,
+    /// The call running or the last one halted, until R0 was read back
+    call: Option<AlgorithmCall>,
+    /// DHCSR reads since the core was resumed
+    polls: usize,
+}
+
+/// Recognises flash algorithm calls from the memory accesses of a log
+#[derive(Clone, Debug, Default)]
+pub struct FlashAlgorithmAnalyzer {
+    /// Everything written below the System region
+    ram: MemoryImage,
+    cores: BTreeMap<ApAddress, Core>,
+    calls: Vec<AlgorithmCall>,
+}
+
+impl FlashAlgorithmAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, with the core register access it completed
+    /// as reported by [`crate::core_regs::CoreRegisterTracker`]
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+        core_access: Option<&CoreRegisterAccess>,
+        position: LogPosition,
+    ) {
+        if direction == Direction::Write && access.address < SYSTEM_REGION {
+            self.ram.apply_access(direction, access);
+        }
+
+        let core = self.cores.entry(ap).or_default();
+        if let Some(core_access) = core_access {
+            match core_access.direction {
+                Direction::Write => {
+                    if let Some(value) = core_access.value {
+                        core.registers.insert(core_access.register, value);
+                    }
+                }
+                // Reading back the return value completes a halted call
+                Direction::Read if core_access.register == CoreRegister(0) => {
+                    if core.call.as_ref().is_some_and(|call| call.halt.is_some()) {
+                        let mut call = core.call.take().unwrap();
+                        call.result = core_access.value;
+                        self.calls.push(call);
+                    }
+                }
+                Direction::Read => {}
+            }
+        }
+
+        match access.register {
+            Some(ArmRegister::DhcsrWrite(dhcsr)) if is_resume(dhcsr) => {
+                self.calls.extend(core.call.take());
+                let registers = std::mem::take(&mut core.registers);
+                let Some(&pc) = registers.get(&CoreRegister::PC) else {
+                    return;
+                };
+                let arg = |n| registers.get(&CoreRegister(n)).copied();
+                core.call = Some(AlgorithmCall {
+                    ap,
+                    resumed: position,
+                    entry: pc,
+                    args: [arg(0), arg(1), arg(2), arg(3)],
+                    sp: registers.get(&CoreRegister::SP).copied(),
+                    return_address: registers.get(&CoreRegister::LR).copied(),
+                    // Thumb entry points have bit 0 set
+                    blob: self.ram.region(pc & !1),
+                    halt: None,
+                    result: None,
+                });
+                core.polls = 0;
+            }
+            Some(ArmRegister::Dhcsr(dhcsr)) => {
+                let Some(call) = core.call.as_mut().filter(|call| call.halt.is_none()) else {
+                    return;
+                };
+                core.polls += 1;
+                if dhcsr.s_halt() {
+                    call.halt = Some(AlgorithmHalt {
+                        position,
+                        polls: core.polls,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// All the calls recognised, in order
+    pub fn finish(mut self) -> Vec<AlgorithmCall> {
+        for core in self.cores.values_mut() {
+            self.calls.extend(core.call.take());
+        }
+        self.calls.sort_by_key(|call| call.resumed.line);
+        self.calls
+    }
+}
+
+/// A keyed DHCSR write letting the core run, not single-stepping
+fn is_resume(dhcsr: DhcsrWrite) -> bool {
+    dhcsr.has_dbgkey() && dhcsr.c_debugen() && !dhcsr.c_halt() && !dhcsr.c_step()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_regs::CoreRegisterTracker;
+    use crate::test_util::memory_accesses;
+
+    #[test]
+    fn algorithm_call() {
+        let accesses = memory_accesses(&[
+            // Download the algorithm
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000012",
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP WR A:3 ACK:1 OK Data:hBE00BE00",
+            "AP WR A:3 ACK:1 OK Data:h47702000",
+            "AP WR A:3 ACK:1 OK Data:h47702001",
+            // Set up registers through the banked DHCSR/DCRSR/DCRDR/DEMCR
+            "AP WR A:1 ACK:1 OK Data:hE000EDF0",
+            "DP WR A:2 ACK:1 OK Data:h00000010",
+            "AP WR A:2 ACK:1 OK Data:h08000000",
+            "AP WR A:1 ACK:1 OK Data:h00010000",
+            "AP WR A:2 ACK:1 OK Data:h00B71B00",
+            "AP WR A:1 ACK:1 OK Data:h00010001",
+            "AP WR A:2 ACK:1 OK Data:h20001000",
+            "AP WR A:1 ACK:1 OK Data:h0001000D",
+            "AP WR A:2 ACK:1 OK Data:h20000001",
+            "AP WR A:1 ACK:1 OK Data:h0001000E",
+            "AP WR A:2 ACK:1 OK Data:h20000005",
+            "AP WR A:1 ACK:1 OK Data:h0001000F",
+            // Resume and poll for the halt
+            "AP WR A:0 ACK:1 OK Data:hA05F0001",
+            "AP RD A:0 ACK:1 OK Data:h00000000",
+            "AP RD A:0 ACK:1 OK Data:h00010001",
+            "DP RD A:3 ACK:1 OK Data:h00030003",
+            // Read back R0
+            "AP WR A:1 ACK:1 OK Data:h00000000",
+            "AP RD A:2 ACK:1 OK Data:h00000000",
+            "DP RD A:3 ACK:1 OK Data:h00000000",
+            // A plain resume isn't a call
+            "AP WR A:0 ACK:1 OK Data:hA05F0001",
+        ]);
+        // Each line is a block of its own, 10 ms after the previous one
+        let mut core_registers = CoreRegisterTracker::new();
+        let mut analyzer = FlashAlgorithmAnalyzer::new();
+        for (line, ap, direction, access) in accesses {
+            let core_access = core_registers.push(ap, direction, &access);
+            let position = LogPosition {
+                line,
+                timestamp: Some(Timestamp {
+                    hours: 12,
+                    minutes: 0,
+                    seconds: 0,
+                    millis: (line as u16 - 1) * 10,
+                }),
+            };
+            analyzer.push(ap, direction, &access, core_access.as_ref(), position);
+        }
+        let calls = analyzer.finish();
+        assert_eq!(calls.len(), 1);
+        let call = &calls[0];
+        assert_eq!(call.entry, 0x2000_0005);
+        assert_eq!(
+            call.args,
+            [Some(0x0800_0000), Some(0x00B7_1B00), None, None]
+        );
+        assert_eq!(call.sp, Some(0x2000_1000));
+        assert_eq!(call.return_address, Some(0x2000_0001));
+        assert_eq!(call.resumed.line, 19);
+        let blob = call.blob.as_ref().unwrap();
+        assert_eq!((blob.address, blob.data.len()), (0x2000_0000, 12));
+
+        let halt = call.halt.unwrap();
+        assert_eq!(halt.position.line, 22);
+        assert_eq!(halt.polls, 2);
+        assert_eq!(call.duration(), Some(Duration::from_millis(30)));
+        assert_eq!(call.result, Some(0));
+        assert_eq!(
+            call.to_string(),
+            "line 19: AP:00 call 20000005(08000000, 00B71B00, ?, ?) SP=20001000 LR=20000001 \
+             -> halted at line 22 after 30 ms (2 polls) R0=00000000"
+        );
+    }
+}
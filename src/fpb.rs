@@ -0,0 +1,199 @@
+//! Breakpoints armed through the Flash Patch and Breakpoint unit
+
+use crate::arm_regs::{ArmRegister, FpbVersion};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A change to the FPB breakpoints made by the debugger
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BreakpointEvent {
+    /// FP_CTRL was written with ENABLE set
+    Enabled,
+    /// FP_CTRL was written with ENABLE clear
+    Disabled,
+    Set {
+        comparator: u8,
+        address: u32,
+    },
+    /// A comparator was disabled or turned into a remap
+    Cleared {
+        comparator: u8,
+        /// The breakpoint address previously set, if seen
+        address: Option<u32>,
+    },
+}
+
+impl fmt::Display for BreakpointEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakpointEvent::Enabled => f.write_str("FPB enabled"),
+            BreakpointEvent::Disabled => f.write_str("FPB disabled"),
+            BreakpointEvent::Set {
+                comparator,
+                address,
+            } => write!(f, "breakpoint {} set at 0x{:08X}", comparator, address),
+            BreakpointEvent::Cleared {
+                comparator,
+                address: Some(address),
+            } => write!(
+                f,
+                "breakpoint {} cleared (was 0x{:08X})",
+                comparator, address
+            ),
+            BreakpointEvent::Cleared {
+                comparator,
+                address: None,
+            } => write!(f, "breakpoint {} cleared", comparator),
+        }
+    }
+}
+
+/// FPB state of one MEM-AP
+#[derive(Clone, Debug, Default)]
+struct Fpb {
+    /// From FP_CTRL.REV, once read
+    version: Option<FpbVersion>,
+    /// Breakpoint address of each comparator set
+    breakpoints: BTreeMap<u8, u32>,
+}
+
+/// Tracks FP_CTRL and FP_COMPn accesses per MEM-AP. The FP_COMPn format is
+/// taken from FP_CTRL.REV when it was read, and guessed otherwise
+#[derive(Clone, Debug, Default)]
+pub struct BreakpointTracker {
+    fpbs: BTreeMap<ApAddress, Fpb>,
+}
+
+impl BreakpointTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the breakpoint change it made, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<BreakpointEvent> {
+        let fpb = self.fpbs.entry(ap).or_default();
+        match (access.register?, direction) {
+            (ArmRegister::FpCtrl(ctrl), Direction::Read) => {
+                fpb.version = ctrl.version();
+                None
+            }
+            (ArmRegister::FpCtrl(ctrl), Direction::Write) if ctrl.key() => Some(if ctrl.enable() {
+                BreakpointEvent::Enabled
+            } else {
+                BreakpointEvent::Disabled
+            }),
+            (ArmRegister::FpComp(comparator, comp), Direction::Write) => {
+                let version = fpb.version.unwrap_or_else(|| comp.guess_version());
+                Some(match comp.breakpoint(version) {
+                    Some(address) => {
+                        fpb.breakpoints.insert(comparator, address);
+                        BreakpointEvent::Set {
+                            comparator,
+                            address,
+                        }
+                    }
+                    None => BreakpointEvent::Cleared {
+                        comparator,
+                        address: fpb.breakpoints.remove(&comparator),
+                    },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Breakpoint addresses currently set through an AP, by comparator
+    pub fn breakpoints(&self, ap: ApAddress) -> impl Iterator<Item = (u8, u32)> + '_ {
+        self.fpbs
+            .get(&ap)
+            .into_iter()
+            .flat_map(|fpb| fpb.breakpoints.iter().map(|(&n, &address)| (n, address)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arm_regs::FpComp;
+    use crate::test_util::track;
+
+    #[test]
+    fn fp_comp_formats() {
+        // FPBv1, breakpoint on the upper halfword
+        let comp = FpComp(0x8800_0101);
+        assert_eq!(comp.guess_version(), FpbVersion::V1);
+        assert_eq!(comp.breakpoint(FpbVersion::V1), Some(0x0800_0102));
+        // FPBv1 remap
+        assert_eq!(FpComp(0x0800_0101).breakpoint(FpbVersion::V1), None);
+        // FPBv2
+        let comp = FpComp(0x0800_0123);
+        assert_eq!(comp.guess_version(), FpbVersion::V2);
+        assert_eq!(comp.breakpoint(FpbVersion::V2), Some(0x0800_0122));
+        assert_eq!(FpComp(0x0800_0122).breakpoint(FpbVersion::V2), None);
+        // Disabled comparators
+        let comp = FpComp(0x0800_0100);
+        assert_eq!(comp.guess_version(), FpbVersion::V1);
+        assert_eq!(comp.to_string(), "(replace:0, comp:08000100, enable:0)");
+        assert_eq!(FpComp(0).guess_version(), FpbVersion::V1);
+        assert_eq!(FpComp(0x0800_0122).guess_version(), FpbVersion::V2);
+
+        assert_eq!(FpComp::index(0xE000_2008), Some(0));
+        assert_eq!(FpComp::index(0xE000_2014), Some(3));
+        assert_eq!(FpComp::index(0xE000_2FD0), None);
+    }
+
+    #[test]
+    fn breakpoint_events() {
+        let mut tracker = BreakpointTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000012",
+                // FP_CTRL reads as FPBv2
+                "AP WR A:1 ACK:1 OK Data:hE0002000",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h10000260",
+                "AP WR A:1 ACK:1 OK Data:hE0002000",
+                "AP WR A:3 ACK:1 OK Data:h00000003",
+                // FP_REMAP, FP_COMP0 and FP_COMP1 auto-incrementing
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+                "AP WR A:3 ACK:1 OK Data:h08000401",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+                "AP WR A:1 ACK:1 OK Data:hE0002008",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(
+            events,
+            [
+                BreakpointEvent::Enabled,
+                BreakpointEvent::Set {
+                    comparator: 0,
+                    address: 0x0800_0400
+                },
+                BreakpointEvent::Cleared {
+                    comparator: 1,
+                    address: None
+                },
+                BreakpointEvent::Cleared {
+                    comparator: 0,
+                    address: Some(0x0800_0400)
+                },
+            ]
+        );
+        assert_eq!(tracker.breakpoints(ApAddress::Apsel(0)).count(), 0);
+        assert_eq!(
+            events[3].to_string(),
+            "breakpoint 0 cleared (was 0x08000400)"
+        );
+    }
+}
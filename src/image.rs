@@ -0,0 +1,247 @@
+//! Sparse target memory image, reconstructed from memory transactions
+
+use crate::decoder::MemoryAccess;
+use crate::memory::Transaction;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Every byte of target memory seen written or read, later accesses replace
+/// earlier values. Byte lanes are taken as little-endian
+#[derive(Clone, Debug, Default)]
+pub struct MemoryImage {
+    bytes: BTreeMap<u32, Byte>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct Byte {
+    value: u8,
+    written: bool,
+    read: bool,
+}
+
+/// A contiguous range of known bytes
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Region {
+    pub address: u32,
+    pub data: Vec<u8>,
+    /// Some of the bytes were written by the debugger
+    pub written: bool,
+    /// Some of the bytes were read from the target
+    pub read: bool,
+}
+
+impl Region {
+    /// Address of the last byte
+    pub fn end_address(&self) -> u32 {
+        self.address + (self.data.len() as u32 - 1)
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match (self.read, self.written) {
+            (true, true) => "RW",
+            (true, false) => "R",
+            _ => "W",
+        };
+        write!(
+            f,
+            "{:08X}..{:08X} {:>8} bytes  {}",
+            self.address,
+            self.end_address(),
+            self.data.len(),
+            access
+        )
+    }
+}
+
+impl MemoryImage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Apply the accesses of a transaction, a faulted transaction still
+    /// performed the accesses it holds
+    pub fn apply(&mut self, transaction: &Transaction) {
+        for access in transaction.accesses.iter() {
+            self.apply_access(transaction.direction, access);
+        }
+    }
+
+    /// Apply a single memory access
+    pub fn apply_access(&mut self, direction: Direction, access: &MemoryAccess) {
+        for (address, value) in access_bytes(access) {
+            let byte = self.bytes.entry(address).or_insert(Byte {
+                value,
+                written: false,
+                read: false,
+            });
+            byte.value = value;
+            match direction {
+                Direction::Write => byte.written = true,
+                Direction::Read => byte.read = true,
+            }
+        }
+    }
+
+    /// The byte at an address, if known
+    pub fn byte(&self, address: u32) -> Option<u8> {
+        self.bytes.get(&address).map(|byte| byte.value)
+    }
+
+    /// The contiguous region of known bytes holding an address
+    pub fn region(&self, address: u32) -> Option<Region> {
+        // Walk down from the address to the first byte of the region
+        let mut start = None;
+        for (&byte_address, _) in self.bytes.range(..=address).rev() {
+            match start {
+                None if byte_address != address => return None,
+                Some(start) if byte_address + 1 != start => break,
+                _ => start = Some(byte_address),
+            }
+        }
+        let start = start?;
+
+        let mut region = Region {
+            address: start,
+            data: Vec::new(),
+            written: false,
+            read: false,
+        };
+        for (&byte_address, byte) in self.bytes.range(start..) {
+            if start.checked_add(region.data.len() as u32) != Some(byte_address) {
+                break;
+            }
+            region.data.push(byte.value);
+            region.written |= byte.written;
+            region.read |= byte.read;
+        }
+        Some(region)
+    }
+
+    /// The contiguous regions of known bytes, in address order
+    pub fn regions(&self) -> Vec<Region> {
+        let mut regions: Vec<Region> = Vec::new();
+        for (&address, byte) in self.bytes.iter() {
+            match regions.last_mut() {
+                Some(region)
+                    if region.address.checked_add(region.data.len() as u32) == Some(address) =>
+                {
+                    region.data.push(byte.value);
+                    region.written |= byte.written;
+                    region.read |= byte.read;
+                }
+                _ => regions.push(Region {
+                    address,
+                    data: vec![byte.value],
+                    written: byte.written,
+                    read: byte.read,
+                }),
+            }
+        }
+        regions
+    }
+}
+
+/// The bytes moved by an access, from the byte lanes of the data register.
+/// An unknown size is taken as a word access
+fn access_bytes(access: &MemoryAccess) -> impl Iterator<Item = (u32, u8)> + '_ {
+    let len = match access.size {
+        _ if access.packed => 4,
+        Some(size) => size.min(4),
+        None => 4,
+    };
+    (0..len).map(move |offset| {
+        let address = access.address.wrapping_add(offset);
+        let lane = address & 0x3;
+        (address, (access.value >> (lane * 8)) as u8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Decoder;
+    use crate::memory::TransactionBuilder;
+    use crate::parser;
+
+    fn image(lines: &[&str]) -> MemoryImage {
+        let mut decoder = Decoder::new();
+        let mut builder = TransactionBuilder::new();
+        let mut image = MemoryImage::new();
+        for line in lines {
+            let decoded = decoder.decode(parser::parse(line).unwrap().1);
+            if let Some(transaction) = builder.push(&decoded) {
+                image.apply(&transaction);
+            }
+        }
+        if let Some(transaction) = builder.finish() {
+            image.apply(&transaction);
+        }
+        image
+    }
+
+    #[test]
+    fn word_and_byte_lanes() {
+        let image = image(&[
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000052",
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP WR A:3 ACK:1 OK Data:h44332211",
+            "AP WR A:3 ACK:1 OK Data:h88776655",
+            // Byte write to 0x20000011, data on lane 1
+            "AP WR A:0 ACK:1 OK Data:h23000050",
+            "AP WR A:1 ACK:1 OK Data:h20000011",
+            "AP WR A:3 ACK:1 OK Data:h0000AA00",
+            // Halfword read of 0x20000012
+            "AP WR A:0 ACK:1 OK Data:h23000051",
+            "AP WR A:1 ACK:1 OK Data:h20000012",
+            "AP RD A:3 ACK:1 OK Data:h00000000",
+            "DP RD A:3 ACK:1 OK Data:hCCBB0000",
+        ]);
+        assert_eq!(image.byte(0x2000_0000), Some(0x11));
+        assert_eq!(image.byte(0x2000_0007), Some(0x88));
+        assert_eq!(image.byte(0x2000_0010), None);
+
+        let regions = image.regions();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].address, 0x2000_0000);
+        assert_eq!(
+            regions[0].data,
+            [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]
+        );
+        assert!(regions[0].written && !regions[0].read);
+        assert_eq!(regions[1].address, 0x2000_0011);
+        assert_eq!(regions[1].data, [0xAA, 0xBB, 0xCC]);
+        assert!(regions[1].written && regions[1].read);
+        assert_eq!(
+            regions[1].to_string(),
+            "20000011..20000013        3 bytes  RW"
+        );
+
+        assert_eq!(image.region(0x2000_0000), Some(regions[0].clone()));
+        assert_eq!(image.region(0x2000_0005), Some(regions[0].clone()));
+        assert_eq!(image.region(0x2000_0013), Some(regions[1].clone()));
+        assert_eq!(image.region(0x2000_0008), None);
+        assert_eq!(image.region(0x2000_0010), None);
+    }
+
+    #[test]
+    fn packed_halfwords() {
+        let image = image(&[
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000021",
+            "AP WR A:1 ACK:1 OK Data:h20000100",
+            "AP WR A:3 ACK:1 OK Data:h44332211",
+            "AP WR A:3 ACK:1 OK Data:h88776655",
+        ]);
+        let regions = image.regions();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].data.len(), 8);
+    }
+}
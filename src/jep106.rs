@@ -0,0 +1,93 @@
+//! JEP106 manufacturer identification codes
+
+use std::fmt;
+
+/// A JEP106 manufacturer code, as found in the 11-bit DESIGNER fields of the
+/// DPIDR, AP IDR and TARGETID registers
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Jep106Code {
+    /// Continuation code, the number of 0x7F bytes preceding the identity code
+    pub cc: u8,
+    /// Identity code, without the parity bit
+    pub id: u8,
+}
+
+impl Jep106Code {
+    pub const ARM: Jep106Code = Jep106Code::new(4, 0x3B);
+
+    pub const fn new(cc: u8, id: u8) -> Self {
+        Self { cc, id }
+    }
+
+    /// Split an 11-bit DESIGNER field into its continuation code (bits 10:7)
+    /// and identity code (bits 6:0)
+    pub const fn from_designer(designer: u16) -> Self {
+        Self {
+            cc: ((designer >> 7) & 0xF) as u8,
+            id: (designer & 0x7F) as u8,
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        MANUFACTURERS
+            .iter()
+            .find(|(code, _)| code == self)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Display for Jep106Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({}:{:02X})", name, self.cc, self.id),
+            None => write!(f, "unknown ({}:{:02X})", self.cc, self.id),
+        }
+    }
+}
+
+/// The manufacturers commonly found in debug components
+const MANUFACTURERS: &[(Jep106Code, &str)] = &[
+    (Jep106Code::new(0, 0x01), "AMD"),
+    (Jep106Code::new(0, 0x04), "Fujitsu"),
+    (Jep106Code::new(0, 0x07), "Hitachi"),
+    (Jep106Code::new(0, 0x09), "Intel"),
+    (Jep106Code::new(0, 0x0E), "Freescale (Motorola)"),
+    (Jep106Code::new(0, 0x10), "NEC"),
+    (Jep106Code::new(0, 0x15), "NXP (Philips)"),
+    (Jep106Code::new(0, 0x17), "Texas Instruments"),
+    (Jep106Code::new(0, 0x18), "Toshiba"),
+    (Jep106Code::new(0, 0x1C), "Mitsubishi"),
+    (Jep106Code::new(0, 0x1F), "Atmel"),
+    (Jep106Code::new(0, 0x20), "STMicroelectronics"),
+    (Jep106Code::new(0, 0x29), "Microchip Technology"),
+    (Jep106Code::new(0, 0x2C), "Micron Technology"),
+    (Jep106Code::new(0, 0x34), "Cypress"),
+    (Jep106Code::new(0, 0x41), "Infineon (Siemens)"),
+    (Jep106Code::new(0, 0x49), "Xilinx"),
+    (Jep106Code::new(0, 0x4E), "Samsung"),
+    (Jep106Code::new(0, 0x6E), "Altera"),
+    (Jep106Code::new(0, 0x70), "Qualcomm"),
+    (Jep106Code::new(2, 0x44), "Nordic VLSI ASA"),
+    (Jep106Code::ARM, "ARM Ltd"),
+    (Jep106Code::new(9, 0x13), "Raspberry Pi Trading"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_designer() {
+        assert_eq!(Jep106Code::from_designer(0x23B), Jep106Code::ARM);
+        assert_eq!(Jep106Code::from_designer(0x00E), Jep106Code::new(0, 0x0E));
+        assert_eq!(Jep106Code::from_designer(0x493), Jep106Code::new(9, 0x13));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Jep106Code::ARM.name(), Some("ARM Ltd"));
+        assert_eq!(Jep106Code::ARM.to_string(), "ARM Ltd (4:3B)");
+        assert_eq!(Jep106Code::new(0, 0x7E).name(), None);
+        assert_eq!(Jep106Code::new(0, 0x7E).to_string(), "unknown (0:7E)");
+    }
+}
@@ -0,0 +1,28 @@
+//! Parse and decode SWD protocol operation logs from DIGILENT WaveForms's
+//! SWD protocol analyzer.
+//!
+//! Lines are parsed with [`parser::LogParser`] and then fed to a
+//! [`decoder::Decoder`], which tracks the debug port state and yields typed
+//! register accesses.
+
+pub mod ap_regs;
+pub mod arm_regs;
+pub mod core_regs;
+pub mod decoder;
+pub mod diagnostic;
+pub mod dp;
+pub mod dp_regs;
+pub mod dwt;
+pub mod events;
+pub mod export;
+pub mod flash_algo;
+pub mod fpb;
+pub mod image;
+pub mod jep106;
+pub mod memory;
+pub mod mpu;
+pub mod parser;
+pub mod s32k3xx;
+pub mod swo;
+#[cfg(test)]
+mod test_util;
@@ -1,12 +1,21 @@
-use crate::parser::{AccessRegister, Direction};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use colored::Colorize;
-use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter};
 use std::path::PathBuf;
-
-mod parser;
+use waveforms_swd_protocol_parser::{
+    ap_regs,
+    decoder::{Access, ApAccess, ApRegister, Decoded, Decoder, DpRegister},
+    diagnostic::Diagnostic,
+    dp::{ApAddress, PostedRead},
+    events::{DebugEvent, DebugEventTracker},
+    export,
+    flash_algo::{FlashAlgorithmAnalyzer, LogPosition},
+    image::MemoryImage,
+    memory::{memory_access, Transaction, TransactionBuilder, TransactionResult},
+    parser::{Direction, Line, LogParser, Response},
+    s32k3xx,
+};
 
 /// Parse WaveForms SWD protocol logs
 #[derive(Parser, Debug, Clone)]
@@ -14,28 +23,72 @@ mod parser;
 struct Opts {
     /// Input WaveForms SWD log file to read
     pub input: PathBuf,
+
+    /// Output view
+    #[clap(long, value_enum, default_value_t = View::Packets)]
+    pub view: View,
+
+    /// Decode AP accesses as ADIv6, for captures without a DPv3 DPIDR read
+    #[clap(long)]
+    pub adiv6: bool,
+
+    /// Export the reconstructed memory image as Intel HEX
+    #[clap(long, value_name = "PATH")]
+    pub export_ihex: Option<PathBuf>,
+
+    /// Export the reconstructed memory image as Motorola S-record
+    #[clap(long, value_name = "PATH")]
+    pub export_srec: Option<PathBuf>,
+
+    /// Export each contiguous region of the memory image as a raw binary file
+    /// named by its start address into a directory
+    #[clap(long, value_name = "DIR")]
+    pub export_bin: Option<PathBuf>,
+
+    /// Export the reconstructed memory image as an ELF file with one segment
+    /// per region
+    #[clap(long, value_name = "PATH")]
+    pub export_elf: Option<PathBuf>,
+
+    /// Exit with a non-zero status if any operation couldn't be decoded, lines
+    /// that aren't packets (banners, headers, comments) are only counted
+    #[clap(long)]
+    pub strict: bool,
 }
 
-// S32K344 specifics
-const APB_AP_ID: u8 = 1;
-const CM7_0_AHB_AP_ID: u8 = 4;
-const MDM_AP_ID: u8 = 6;
-const SDA_AP_ID: u8 = 7;
-const S32K3XX_AP_IDS: [u8; 4] = [APB_AP_ID, CM7_0_AHB_AP_ID, MDM_AP_ID, SDA_AP_ID];
+#[derive(ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+enum View {
+    /// One line per SWD packet
+    Packets,
+    /// One line per memory transaction
+    Memory,
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::parse();
 
-    let file = File::open(opts.input)?;
+    let file = File::open(&opts.input)?;
 
     let mut reader = BufReader::new(file);
 
     let mut line_buf = String::new();
 
-    let mut observed_aps = HashSet::new();
-
-    let mut dp_select_reg = dp_regs::Select(0);
-    let mut tar_reg = ap_regs::Tar(0);
+    let mut log_parser = LogParser::new();
+    let mut decoder = if opts.adiv6 {
+        Decoder::adiv6()
+    } else {
+        Decoder::new()
+    };
+    let mut transactions = TransactionBuilder::new();
+    let mut image = MemoryImage::new();
+    let mut debug_events = DebugEventTracker::new();
+    let mut flash_algorithms = FlashAlgorithmAnalyzer::new();
+    // Debug events of the transaction being built, shown after it
+    let mut transaction_events = Vec::new();
+    let mut diagnostics = Vec::new();
+    // Lines that aren't understood, e.g. banners and comments
+    let mut unrecognised_lines = 0;
+    let packets = opts.view == View::Packets;
 
     loop {
         line_buf.clear();
@@ -46,615 +99,428 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         let line = line_buf.trim();
-        print!("{}", line);
+        if packets {
+            print!("{}", line);
+        }
+
+        let prev_block = log_parser.block();
+        let parsed = log_parser.parse_line(line);
 
-        if let Ok((_, op)) = parser::parse(line) {
-            print!("  ");
+        if let Some(Line::Block(block)) = parsed.filter(|_| packets) {
+            print!("  (block {}", block.index);
+            if let Some(prev) = prev_block {
+                print!(
+                    ", +{} ms",
+                    block.timestamp.duration_since(prev.timestamp).as_millis()
+                );
+            }
+            print!(")");
+        }
 
-            match op.direction {
-                Direction::Read => print!("<-- "),
-                Direction::Write => print!("{} ", "-->".yellow().bold()),
+        if let Some(Line::Sequence(sequence)) = parsed {
+            let event = decoder.sequence(sequence);
+            if packets {
+                print!("  {} {}    ({})", "==".cyan(), sequence, event);
             }
+        }
 
-            match op.access {
-                AccessRegister::DebugPort => {
-                    let address = op.address_2_3 << 2;
-                    print!("R:{:02X}", address);
-
-                    match address {
-                        // 0x00
-                        dp_regs::IdCode::ADDRESS if op.direction == Direction::Read => {
-                            let idcode = dp_regs::IdCode(op.data);
-                            print!(
-                                " {}    Version:{} PARTNO:{} DESIGNER:{}",
-                                dp_regs::IdCode::NAME,
-                                idcode.version(),
-                                idcode.partno(),
-                                idcode.designer(),
-                            );
-                        }
-                        dp_regs::Abort::ADDRESS if op.direction == Direction::Write => {
-                            let abort = dp_regs::Abort(op.data);
-                            print!(
-                                " {}     DAPABORT:{} STKCMPCLR:{} STKERRCLR:{} WDERRCLR:{} ORUNERRCLR:{}",
-                                dp_regs::Abort::NAME,
-                                if abort.dapabort() { 1 } else { 0 },
-                                if abort.stkcmpclr() { 1 } else { 0 },
-                                if abort.stkerrclr() { 1 } else { 0 },
-                                if abort.wderrclr() { 1 } else { 0 },
-                                if abort.orunerrclr() { 1 } else { 0 },
-                            );
-                        }
-
-                        // 0x04
-                        dp_regs::CtrlStat::ADDRESS if !dp_select_reg.ctrlsel() => {
-                            let ctrlstat = dp_regs::CtrlStat(op.data);
-                            print!(
-                                " {} READOK:{} WDATAERR:{} TRNCNT:{} CDBGRSTREQ:{} (ACK:{}) CDBGPWRUPREQ:{} (ACK:{}) CSYSPWRUPREQ:{} (ACK:{})",
-                                dp_regs::CtrlStat::NAME,
-                                if ctrlstat.readok() { 1 } else { 0 },
-                                if ctrlstat.wdataerr() { 1 } else { 0 },
-                                ctrlstat.trncnt(),
-                                if ctrlstat.cdbgrstreq() { 1 } else { 0 },
-                                if ctrlstat.cdbgrstack() { 1 } else { 0 },
-                                if ctrlstat.cdbgpwrupreq() { 1 } else { 0 },
-                                if ctrlstat.cdbgpwrupack() { 1 } else { 0 },
-                                if ctrlstat.csyspwrupreq() { 1 } else { 0 },
-                                if ctrlstat.csyspwrupack() { 1 } else { 0 },
-                            );
-                        }
-                        dp_regs::Wcr::ADDRESS if dp_select_reg.ctrlsel() => {
-                            let wcr = dp_regs::Wcr(op.data);
-                            print!(
-                                " {}    PRESCALER:{} WIREMODE:{} TURNROUND:{}",
-                                dp_regs::Wcr::NAME,
-                                wcr.prescaler(),
-                                wcr.wiremode(),
-                                wcr.turnround(),
-                            );
-                        }
-
-                        // 0x08
-                        dp_regs::Select::ADDRESS if op.direction == Direction::Write => {
-                            let select = dp_regs::Select(op.data);
-
-                            observed_aps.insert(select.apsel() as u8);
-
-                            let mut apsel = format!("{:02X}", select.apsel()).normal();
-                            if select.apsel() as u8 == MDM_AP_ID
-                                || select.apsel() as u8 == SDA_AP_ID
-                            {
-                                apsel = apsel.bright_red();
-                            } else if S32K3XX_AP_IDS.contains(&(select.apsel() as u8)) {
-                                apsel = apsel.bright_yellow();
-                            }
-
-                            let s32k3xx_ap = match select.apsel() as u8 {
-                                APB_AP_ID => "    (APB_AP)",
-                                CM7_0_AHB_AP_ID => "    (CM7_0_AHB_AP)",
-                                MDM_AP_ID => "    (MDM_AP)",
-                                SDA_AP_ID => "    (SDA_API)",
-                                _ => "",
-                            };
-
-                            print!(
-                                " {}    APSEL:{} APBANKSEL:{:02X} CTRLSEL:{}{}",
-                                dp_regs::Select::NAME,
-                                //select.apsel()
-                                apsel,
-                                select.apbanksel(),
-                                if select.ctrlsel() { 1 } else { 0 },
-                                s32k3xx_ap,
-                            );
-                            // TODO is this right?
-                            dp_select_reg = select;
-                        }
-                        dp_regs::Resend::ADDRESS if op.direction == Direction::Read => {
-                            print!(" {}    {:08X}", dp_regs::Resend::NAME, op.data);
-                        }
-
-                        // 0x0C
-                        dp_regs::RdBuff::ADDRESS if op.direction == Direction::Read => {
-                            print!(" {}    {:08X}", dp_regs::RdBuff::NAME, op.data);
-                        }
-
-                        _ => {
-                            panic!("Unhandled SW-DP register access");
-                        }
-                    }
+        if let Some(Line::Operation(op)) = parsed {
+            let decoded = decoder.decode(op);
+            let event = memory_access(&decoded).and_then(|(ap, direction, access)| {
+                let event = debug_events.push(ap, direction, &access);
+                let core_access = match &event {
+                    Some(DebugEvent::CoreRegister(core_access)) => Some(core_access),
+                    _ => None,
+                };
+                let position = LogPosition {
+                    line: log_parser.line_number(),
+                    timestamp: log_parser.block().map(|block| block.timestamp),
+                };
+                flash_algorithms.push(ap, direction, &access, core_access, position);
+                event
+            });
+            if packets {
+                print_decoded(&decoder, &decoded);
+                if let Some(event) = &event {
+                    print!("  {} {}", "**".green(), event);
                 }
-                AccessRegister::AccessPort => {
-                    let address = mem_ap_address(dp_select_reg.apbanksel() as u8, op.address_2_3);
-
-                    let is_32k3xx = dp_select_reg.apsel() as u8 == MDM_AP_ID
-                        || dp_select_reg.apsel() as u8 == SDA_AP_ID;
-
-                    if is_32k3xx {
-                        print!("R:{}", format!("{:02X}", address).bright_red());
-                    } else {
-                        print!("R:{:02X}", address);
-                    }
-
-                    if !is_32k3xx {
-                        match address {
-                            ap_regs::Idr::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Idr::NAME, op.data);
-                            }
-                            ap_regs::Tar::ADDRESS => {
-                                tar_reg.set_addr(op.data);
-                                print!(" {}       {:08X}", ap_regs::Tar::NAME, op.data);
-                            }
-                            ap_regs::Csw::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Csw::NAME, op.data);
-                            }
-                            ap_regs::Drw::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Drw::NAME, op.data);
-
-                                match tar_reg.addr() {
-                                    arm_regs::Dhcsr::ADDRESS => {
-                                        let dhcsr = arm_regs::Dhcsr(op.data);
-                                        print!("    ");
-                                        dhcsr.min_print();
-                                        //print!("\n{:?}", dhcsr);
-                                    }
-                                    arm_regs::Demcr::ADDRESS => {
-                                        let demcr = arm_regs::Demcr(op.data);
-                                        print!("    ");
-                                        demcr.min_print();
-                                        //print!("\n{:?}", demcr);
-                                    }
-                                    arm_regs::Aircr::ADDRESS => {
-                                        let reg = arm_regs::Aircr(op.data);
-                                        print!("    ");
-                                        reg.min_print();
-                                        //print!("\n{:?}", reg);
-                                    }
-                                    _ => (),
-                                }
-                            }
-                            ap_regs::Bd0::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Bd0::NAME, op.data);
-                                match tar_reg.addr() {
-                                    arm_regs::Dhcsr::ADDRESS => {
-                                        let dhcsr = arm_regs::Dhcsr(op.data);
-                                        print!("    ");
-                                        dhcsr.min_print();
-                                        //print!("\n{:?}", dhcsr);
-                                    }
-                                    arm_regs::Demcr::ADDRESS => {
-                                        let demcr = arm_regs::Demcr(op.data);
-                                        print!("    ");
-                                        demcr.min_print();
-                                        //print!("\n{:?}", demcr);
-                                    }
-                                    arm_regs::Aircr::ADDRESS => {
-                                        let reg = arm_regs::Aircr(op.data);
-                                        print!("    ");
-                                        reg.min_print();
-                                        //print!("\n{:?}", reg);
-                                    }
-                                    _ => (),
-                                }
-                            }
-                            ap_regs::Bd1::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Bd1::NAME, op.data);
-                            }
-                            ap_regs::Bd2::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Bd2::NAME, op.data);
-                            }
-                            ap_regs::Bd3::ADDRESS => {
-                                print!(" {}       {:08X}", ap_regs::Bd3::NAME, op.data);
-                            }
-                            _ => {
-                                print!("           {:08X}", op.data);
-                            }
-                        }
-                    } else {
-                        print!("           {:08X}", op.data);
-                        // is S32K344
-                        if dp_select_reg.apsel() as u8 == SDA_AP_ID {
-                            match address {
-                                0x80 => print!(
-                                    "                           ({})",
-                                    "DBGENCTRL".bright_red()
-                                ),
-                                0x90 => print!(
-                                    "                           ({})",
-                                    "SDAAPRSTCTRL".bright_red()
-                                ),
-                                0xFC => {
-                                    print!("                           ({})", "ID".bright_red())
-                                }
-                                _ => print!("                           ({})", "TODO add reg"),
-                            }
-                        }
-                    }
+            }
+            // The access completing a transaction belongs to the next one
+            if let Some(transaction) = transactions.push(&decoded) {
+                image.apply(&transaction);
+                if !packets {
+                    print_transaction(&transaction, &transaction_events);
                 }
+                transaction_events.clear();
+            }
+            transaction_events.extend(event);
+            if let Some(e) = decoded.error {
+                if packets {
+                    print!("  {} {}", "!!".bright_red(), e);
+                }
+                diagnostics.push(Diagnostic::new(log_parser.line_number(), line, e.into()));
             }
         }
 
-        println!();
-    }
-
-    println!("---------------------------------------------------");
-    println!("Observed APs:");
-    for ap in observed_aps.into_iter() {
-        println!("  {} (0x:{:02X})", ap, ap);
-    }
-
-    Ok(())
-}
-
-fn mem_ap_address(apbanksel: u8, address_2_3: u8) -> u8 {
-    (apbanksel << 4) | (address_2_3 << 2)
-}
-
-mod dp_regs {
-    use bitfield::bitfield;
+        if parsed.is_none() && !line.trim().is_empty() {
+            unrecognised_lines += 1;
+        }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct IdCode(u32);
-        pub designer, _ : 11, 1;
-        pub partno, _ : 27, 12;
-        pub version, _ : 31, 28;
+        if packets {
+            println!();
+        }
     }
 
-    impl IdCode {
-        pub const ADDRESS: u8 = 0x00;
-        pub const NAME: &'static str = "IDCODE";
+    if let Some(transaction) = transactions.finish() {
+        image.apply(&transaction);
+        if !packets {
+            print_transaction(&transaction, &transaction_events);
+        }
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Abort(u32);
-        pub dapabort, _ : 0;
-        pub stkcmpclr, _ : 1;
-        pub stkerrclr, _ : 2;
-        pub wderrclr, _ : 3;
-        pub orunerrclr, _ : 4;
+    let regions = image.regions();
+    if !packets && !regions.is_empty() {
+        println!("---------------------------------------------------");
+        println!("Memory regions:");
+        for region in regions.iter() {
+            println!("  {}", region);
+        }
     }
-
-    impl Abort {
-        pub const ADDRESS: u8 = 0x00;
-        pub const NAME: &'static str = "ABORT";
+    if let Some(path) = &opts.export_ihex {
+        export::write_ihex(&regions, BufWriter::new(File::create(path)?))?;
     }
-
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct CtrlStat(u32);
-        pub readok, _ : 6;
-        pub wdataerr, _ : 7;
-        pub trncnt, _ : 21, 12;
-        /// Debug reset request
-        pub cdbgrstreq, _ : 26;
-        /// Debug reset acknowledge
-        pub cdbgrstack, _ : 27;
-        /// Debug power-up request
-        pub cdbgpwrupreq, _ : 28;
-        /// Debug power-up acknowledge
-        pub cdbgpwrupack, _ : 29;
-        /// System power-up request
-        pub csyspwrupreq, _ : 30;
-        /// System power-up acknowledge
-        pub csyspwrupack, _ : 31;
+    if let Some(path) = &opts.export_srec {
+        export::write_srec(&regions, BufWriter::new(File::create(path)?))?;
     }
-
-    impl CtrlStat {
-        pub const ADDRESS: u8 = 0x04;
-        pub const NAME: &'static str = "CTRL/STAT";
+    if let Some(dir) = &opts.export_bin {
+        export::write_bin(&regions, dir)?;
     }
-
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Wcr(u32);
-        pub prescaler, _ : 2, 0;
-        pub wiremode, _ : 7, 6;
-        pub turnround, _ : 9, 8;
+    if let Some(path) = &opts.export_elf {
+        export::write_elf(&regions, BufWriter::new(File::create(path)?))?;
     }
 
-    impl Wcr {
-        pub const ADDRESS: u8 = 0x04;
-        pub const NAME: &'static str = "WCR";
+    let calls = flash_algorithms.finish();
+    if !calls.is_empty() {
+        println!("---------------------------------------------------");
+        println!("Flash algorithm calls:");
+        for call in calls.iter() {
+            println!("  {}", call);
+            if let Some(blob) = &call.blob {
+                println!("    in {}", blob);
+            }
+        }
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Select(u32);
-        pub ctrlsel, _ : 0;
-        pub apbanksel, _ : 7, 4;
-        pub apsel, _ : 31, 24;
+    let mpu = debug_events.mpu();
+    for ap in mpu.aps() {
+        println!("---------------------------------------------------");
+        println!("Memory protection of AP:{}:", ap);
+        for snapshot in mpu.mpu_snapshot(ap).iter() {
+            println!("  {}", snapshot);
+        }
+        for snapshot in mpu.sau_snapshot(ap).iter() {
+            println!("  {}", snapshot);
+        }
     }
 
-    impl Select {
-        pub const ADDRESS: u8 = 0x08;
-        pub const NAME: &'static str = "SELECT";
+    let multi_drop = decoder.is_multi_drop();
+    for (targetsel, target) in decoder.targets() {
+        // Nothing was decoded while no target was selected
+        if multi_drop && targetsel.is_none() && target.observed_aps().is_empty() {
+            continue;
+        }
+        println!("---------------------------------------------------");
+        match targetsel {
+            Some(targetsel) => println!(
+                "Observed APs of target {:08X} ({}):",
+                targetsel.0, targetsel
+            ),
+            None => println!("Observed APs:"),
+        }
+        for &ap in target.observed_aps() {
+            match ap {
+                ApAddress::Apsel(apsel) => print!("  {} (0x:{:02X})", apsel, apsel),
+                ApAddress::Base(_) => print!("  {}", ap),
+            }
+            if let Some(idr) = target.dp().ap(ap).and_then(|state| state.idr) {
+                print!("    {}", idr);
+            } else if let Some(name) = ap.apsel().and_then(s32k3xx::ap_name) {
+                print!("    ({})", name);
+            }
+            println!();
+        }
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Resend(u32);
+    if !diagnostics.is_empty() {
+        println!("---------------------------------------------------");
+        println!("Diagnostics:");
+        for diagnostic in diagnostics.iter() {
+            println!("  {}", diagnostic);
+        }
     }
-
-    impl Resend {
-        pub const ADDRESS: u8 = 0x08;
-        pub const NAME: &'static str = "RESEND";
+    if unrecognised_lines > 0 {
+        println!("---------------------------------------------------");
+        println!("Skipped {} unrecognised lines", unrecognised_lines);
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct RdBuff(u32);
+    if opts.strict && !diagnostics.is_empty() {
+        std::process::exit(1);
     }
 
-    impl RdBuff {
-        pub const ADDRESS: u8 = 0x0C;
-        pub const NAME: &'static str = "RDBUFF";
-    }
+    Ok(())
 }
 
-mod ap_regs {
-    use bitfield::bitfield;
+/// Number of data words shown for a memory transaction
+const TRANSACTION_WORDS: usize = 8;
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Csw(u32);
+fn print_transaction(transaction: &Transaction, events: &[DebugEvent]) {
+    match transaction.direction {
+        Direction::Read => print!("<-- RD"),
+        Direction::Write => print!("{} WR", "-->".yellow().bold()),
     }
-
-    impl Csw {
-        pub const ADDRESS: u8 = 0x00;
-        pub const NAME: &'static str = "CSW";
+    print!(" AP:{} @{:08X}", transaction.ap, transaction.address());
+    match transaction.size() {
+        Some(size) => print!(" {:>2}-bit", size * 8),
+        None => print!(" ??-bit"),
     }
+    print!(" x{:<4}", transaction.len());
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Tar(u32);
-        pub addr, set_addr : 31, 0;
+    for value in transaction.data().take(TRANSACTION_WORDS) {
+        print!(" {:08X}", value);
     }
-
-    impl Tar {
-        pub const ADDRESS: u8 = 0x04;
-        pub const NAME: &'static str = "TAR";
+    if transaction.len() > TRANSACTION_WORDS {
+        print!(" ... (+{})", transaction.len() - TRANSACTION_WORDS);
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Drw(u32);
-    }
-
-    impl Drw {
-        pub const ADDRESS: u8 = 0x0C;
-        pub const NAME: &'static str = "DRW";
-    }
-
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Bd0(u32);
-    }
-
-    impl Bd0 {
-        pub const ADDRESS: u8 = 0x10;
-        pub const NAME: &'static str = "BD0";
+    if let [access] = transaction.accesses.as_slice() {
+        if let Some(reg) = access.register {
+            print!("    {} {}", reg.name().bright_blue(), reg);
+        }
     }
-
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Bd1(u32);
+    if transaction.result == TransactionResult::Fault {
+        print!("  {} FAULT", "!!".bright_red());
     }
+    println!();
 
-    impl Bd1 {
-        pub const ADDRESS: u8 = 0x14;
-        pub const NAME: &'static str = "BD1";
+    for event in events.iter() {
+        println!("  {} {}", "**".green(), event);
     }
+}
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Bd2(u32);
-    }
+fn print_decoded(decoder: &Decoder, decoded: &Decoded) {
+    let op = &decoded.op;
 
-    impl Bd2 {
-        pub const ADDRESS: u8 = 0x18;
-        pub const NAME: &'static str = "BD2";
-    }
+    let Some(access) = decoded.access else {
+        if let Response::ParityError(_) = op.response {
+            print!("  {}", op.response.to_string().bright_red());
+        }
+        return;
+    };
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Bd3(u32);
-    }
+    print!("  ");
 
-    impl Bd3 {
-        pub const ADDRESS: u8 = 0x1C;
-        pub const NAME: &'static str = "BD3";
+    match op.direction {
+        Direction::Read => print!("<-- "),
+        Direction::Write => print!("{} ", "-->".yellow().bold()),
     }
 
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        pub struct Idr(u32);
+    match access {
+        Access::Dp(reg) => {
+            print!("R:{:02X}", op.address_2_3 << 2);
+            print_dp_register(decoder, &reg);
+        }
+        Access::Ap(ap) => print_ap_access(decoder, &ap),
+        Access::ApRead(read) => print_ap_read(decoder, &read),
     }
 
-    impl Idr {
-        pub const ADDRESS: u8 = 0xFC;
-        pub const NAME: &'static str = "IDR";
+    if let Some(result) = decoded.read_result {
+        print!("    {} AP:{} ", "=>".cyan(), result.ap);
+        print_ap_access(decoder, &result);
     }
 }
 
-mod arm_regs {
-    use bitfield::bitfield;
-    use colored::Colorize;
-
-    bitfield! {
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-        pub struct Dhcsr(u32);
-        impl Debug;
-        pub s_reset_st, _: 25;
-        pub s_retire_st, _: 24;
-        pub s_lockup, _: 19;
-        pub s_sleep, _: 18;
-        pub s_halt, _: 17;
-        pub s_regrdy, _: 16;
-        pub c_maskints, set_c_maskints: 3;
-        pub c_step, set_c_step: 2;
-        pub c_halt, set_c_halt: 1;
-        pub c_debugen, set_c_debugen: 0;
-    }
-
-    impl Dhcsr {
-        pub const ADDRESS: u32 = 0xE000_EDF0;
-        pub const NAME: &'static str = "DHCSR";
-    }
+fn print_dp_register(decoder: &Decoder, reg: &DpRegister) {
+    match reg {
+        DpRegister::DpIdr(dpidr) => print!(" {}     {}", reg.name(), dpidr),
+        DpRegister::Abort(abort) => {
+            print!(
+                " {}     DAPABORT:{} STKCMPCLR:{} STKERRCLR:{} WDERRCLR:{} ORUNERRCLR:{}",
+                reg.name(),
+                abort.dapabort() as u8,
+                abort.stkcmpclr() as u8,
+                abort.stkerrclr() as u8,
+                abort.wderrclr() as u8,
+                abort.orunerrclr() as u8,
+            );
+        }
+        DpRegister::CtrlStat(ctrlstat) => {
+            print!(
+                " {} READOK:{} WDATAERR:{} TRNCNT:{} CDBGRSTREQ:{} (ACK:{}) CDBGPWRUPREQ:{} (ACK:{}) CSYSPWRUPREQ:{} (ACK:{})",
+                reg.name(),
+                ctrlstat.readok() as u8,
+                ctrlstat.wdataerr() as u8,
+                ctrlstat.trncnt(),
+                ctrlstat.cdbgrstreq() as u8,
+                ctrlstat.cdbgrstack() as u8,
+                ctrlstat.cdbgpwrupreq() as u8,
+                ctrlstat.cdbgpwrupack() as u8,
+                ctrlstat.csyspwrupreq() as u8,
+                ctrlstat.csyspwrupack() as u8,
+            );
+        }
+        DpRegister::Wcr(wcr) => {
+            print!(
+                " {}    PRESCALER:{} WIREMODE:{} TURNROUND:{}",
+                reg.name(),
+                wcr.prescaler(),
+                wcr.wiremode(),
+                wcr.turnround(),
+            );
+        }
+        DpRegister::Dlcr(dlcr) => print!(" {}    TURNROUND:{}", reg.name(), dlcr.turnround()),
+        DpRegister::TargetId(targetid) => print!(" {}    {}", reg.name(), targetid),
+        DpRegister::Dlpidr(dlpidr) => {
+            print!(
+                " {}    TINSTANCE:{} PROTVSN:{}",
+                reg.name(),
+                dlpidr.tinstance(),
+                dlpidr.protvsn(),
+            );
+        }
+        DpRegister::EventStat(eventstat) => {
+            print!(" {}    EA:{}", reg.name(), eventstat.ea() as u8);
+        }
+        DpRegister::Select(select) => {
+            let ap = decoder.dp().selected_ap();
+
+            if let ApAddress::Apsel(apsel_id) = ap {
+                let mut apsel = format!("{:02X}", apsel_id).normal();
+                if !decoder.is_mem_ap(ap) {
+                    apsel = apsel.bright_red();
+                } else if s32k3xx::AP_IDS.contains(&apsel_id) {
+                    apsel = apsel.bright_yellow();
+                }
 
-    impl Dhcsr {
-        pub fn min_print(&self) {
+                print!(
+                    " {}    APSEL:{} APBANKSEL:{:02X} DPBANKSEL:{}",
+                    reg.name(),
+                    apsel,
+                    select.apbanksel(),
+                    select.dpbanksel(),
+                );
+            } else {
+                print!(
+                    " {}    ADDR:{:08X} DPBANKSEL:{}",
+                    reg.name(),
+                    select.adiv6_address(),
+                    select.dpbanksel(),
+                );
+            }
+            if let Some(label) = ap_label(decoder, ap) {
+                print!("    ({})", label);
+            }
+        }
+        DpRegister::Select1(select1) => print!(" {}   ADDR:{:08X}", reg.name(), select1.addr()),
+        DpRegister::DpIdr1(dpidr1) => {
+            print!(
+                " {}    ASIZE:{} ERRMODE:{}",
+                reg.name(),
+                dpidr1.asize(),
+                dpidr1.errmode() as u8,
+            );
+        }
+        DpRegister::BasePtr0(baseptr0) => {
             print!(
-                "{} (s_reset_st:{}, s_halt:{}, c_halt:{}, c_debugen:{})",
-                Self::NAME.bright_blue(),
-                self.s_reset_st() as u8,
-                self.s_halt() as u8,
-                self.c_halt() as u8,
-                self.c_debugen() as u8,
+                " {}  VALID:{} PTR:{:08X}",
+                reg.name(),
+                baseptr0.valid() as u8,
+                baseptr0.address(),
             );
         }
+        DpRegister::BasePtr1(baseptr1) => print!(" {}  PTR:{:08X}", reg.name(), baseptr1.ptr()),
+        DpRegister::Resend(resend) => print!(" {}    {:08X}", reg.name(), resend.0),
+        DpRegister::RdBuff(rdbuff) => print!(" {}    {:08X}", reg.name(), rdbuff.0),
+        DpRegister::TargetSel(targetsel) => print!(" {} {}", reg.name(), targetsel),
     }
+}
 
-    bitfield! {
-        /// Debug Exception and Monitor Control Register, DEMCR (see armv7-M Architecture Reference Manual C1.6.5)
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-        pub struct Demcr(u32);
-        impl Debug;
-        /// Global enable for DWT and ITM features
-        pub trcena, set_trcena: 24;
-        /// DebugMonitor semaphore bit
-        pub mon_req, set_mon_req: 19;
-        /// Step the processor?
-        pub mon_step, set_mon_step: 18;
-        /// Sets or clears the pending state of the DebugMonitor exception
-        pub mon_pend, set_mon_pend: 17;
-        /// Enable the DebugMonitor exception
-        pub mon_en, set_mon_en: 16;
-        /// Enable halting debug trap on a HardFault exception
-        pub vc_harderr, set_vc_harderr: 10;
-        /// Enable halting debug trap on a fault occurring during exception entry
-        /// or exception return
-        pub vc_interr, set_vc_interr: 9;
-        /// Enable halting debug trap on a BusFault exception
-        pub vc_buserr, set_vc_buserr: 8;
-        /// Enable halting debug trap on a UsageFault exception caused by a state
-        /// information error, for example an Undefined Instruction exception
-        pub vc_staterr, set_vc_staterr: 7;
-        /// Enable halting debug trap on a UsageFault exception caused by a
-        /// checking error, for example an alignment check error
-        pub vc_chkerr, set_vc_chkerr: 6;
-        /// Enable halting debug trap on a UsageFault caused by an access to a
-        /// Coprocessor
-        pub vc_nocperr, set_vc_nocperr: 5;
-        /// Enable halting debug trap on a MemManage exception.
-        pub vc_mmerr, set_vc_mmerr: 4;
-        /// Enable Reset Vector Catch
-        pub vc_corereset, set_vc_corereset: 0;
+/// Label an AP by its discovered identity, or by the known S32K3xx APs
+fn ap_label(decoder: &Decoder, ap: ApAddress) -> Option<String> {
+    match decoder.ap_kind(ap) {
+        Some(kind) => Some(kind.to_string()),
+        None => ap.apsel().and_then(s32k3xx::ap_name).map(str::to_string),
     }
+}
 
-    impl Demcr {
-        pub const ADDRESS: u32 = 0xE000_EDFC;
-        pub const NAME: &'static str = "DEMCR";
-    }
+fn print_ap_read(decoder: &Decoder, read: &PostedRead) {
+    let name = match read.ap.register(read.address) {
+        Some(ap_regs::Idr::ADDRESS) => Some(ap_regs::Idr::NAME),
+        Some(register) if decoder.is_mem_ap(read.ap) => ap_regs::register_name(register),
+        _ => None,
+    };
+    print_ap_register(decoder, read.ap, read.address, name, "(posted)");
+}
 
-    impl Demcr {
-        pub fn min_print(&self) {
-            print!(
-                "{} (trcena:{}, vc_harderr:{}, vc_corereset:{})",
-                Self::NAME.bright_blue(),
-                self.trcena() as u8,
-                self.vc_harderr() as u8,
-                self.vc_corereset() as u8,
-            );
+fn print_ap_access(decoder: &Decoder, ap: &ApAccess) {
+    print_ap_register(
+        decoder,
+        ap.ap,
+        ap.address,
+        ap.register.name(),
+        &format!("{:08X}", ap.register.value()),
+    );
+
+    if let ApRegister::Csw(csw) = ap.register {
+        let bus = decoder.ap_kind(ap.ap).and_then(|kind| kind.mem_ap_type());
+        if csw.size_bytes() == Some(4) {
+            print!("    {}", csw.display(bus));
+        } else {
+            print!("    {}", csw.display(bus).to_string().bright_yellow());
+        }
+        for warning in csw.warnings() {
+            print!("  {} {}", "!!".bright_red(), warning);
         }
     }
 
-    bitfield! {
-        /// Application Interrupt and Reset Control Register, AIRCR (see armv7-M Architecture Reference Manual B3.2.6)
-        ///
-        /// [`Aircr::vectkey`] must be called before this register can effectively be written!
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-        pub struct Aircr(u32);
-        impl Debug;
-        /// Vector Key. The value 0x05FA must be written to this register, otherwise
-        /// the register write is UNPREDICTABLE.
-        get_vectkeystat, set_vectkey: 31,16;
-        /// Indicates the memory system data endianness:
-        ///
-        /// `0`: little endian.\
-        /// `1`: big endian.
-        ///
-        /// See Endian support on page A3-44 for more information.
-        pub endianness, set_endianness: 15;
-        /// Priority grouping, indicates the binary point position.
-        ///
-        /// For information about the use of this field see Priority grouping on page B1-527.
-        ///
-        /// This field resets to `0b000`.
-        pub prigroup, set_prigroup: 10,8;
-        /// System Reset Request:
-        ///
-        /// `0`: do not request a reset.\
-        /// `1`: request reset.
-        ///
-        /// Writing `1` to this bit asserts a signal to request a reset by the external
-        /// system. The system components that are reset by this request are
-        /// IMPLEMENTATION DEFINED. A Local reset is required as part of a system
-        /// reset request.
-        ///
-        /// A Local reset clears this bit to `0`.
-        ///
-        /// See Reset management on page B1-208 for more information
-        pub sysresetreq, set_sysresetreq: 2;
-        /// Clears all active state information for fixed and configurable exceptions:
-        ///
-        /// `0`: do not clear state information.\
-        /// `1`: clear state information.
-        ///
-        /// The effect of writing a `1` to this bit if the processor is not halted in Debug
-        /// state is UNPREDICTABLE.
-        pub vectclractive, set_vectclractive: 1;
-        /// Writing `1` to this bit causes a local system reset, see Reset management on page B1-559 for
-        /// more information. This bit self-clears.
-        ///
-        /// The effect of writing a `1` to this bit if the processor is not halted in Debug state is UNPREDICTABLE.
-        ///
-        /// When the processor is halted in Debug state, if a write to the register writes a `1` to both
-        /// VECTRESET and SYSRESETREQ, the behavior is UNPREDICTABLE.
-        ///
-        /// This bit is write only.
-        pub vectreset, set_vectreset: 0;
-    }
-
-    impl Aircr {
-        pub const ADDRESS: u32 = 0xE000_ED0C;
-        pub const NAME: &'static str = "AIRCR";
+    if let ApRegister::Idr(idr) = ap.register {
+        print!("    {}", idr);
     }
 
-    impl Aircr {
-        pub fn min_print(&self) {
-            print!(
-                "{} (sysresetreq:{}, vectclractive:{}, vectreset:{})",
-                Self::NAME.bright_blue(),
-                self.sysresetreq() as u8,
-                self.vectclractive() as u8,
-                self.vectreset() as u8,
-            );
+    if let Some(mem) = ap.memory {
+        print!(" @{:08X}", mem.address);
+        if let Some(reg) = mem.register {
+            print!("    {} {}", reg.name().bright_blue(), reg);
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn mem_ap_address_sanity() {
-        assert_eq!(mem_ap_address(0x0, 0b00), 0x00);
-        assert_eq!(mem_ap_address(0x0, 0b01), 0x04);
-        assert_eq!(mem_ap_address(0x0, 0b11), 0x0C);
-        assert_eq!(mem_ap_address(0x1, 0b10), 0x18);
-        assert_eq!(mem_ap_address(0xF, 0b10), 0xF8);
-        assert_eq!(mem_ap_address(0xF, 0b11), 0xFC);
+fn print_ap_register(
+    decoder: &Decoder,
+    ap: ApAddress,
+    address: u16,
+    name: Option<&str>,
+    value: &str,
+) {
+    let address_str = match ap {
+        ApAddress::Apsel(_) => format!("{:02X}", address),
+        ApAddress::Base(_) => format!("{:03X}", address),
+    };
+    if !decoder.is_mem_ap(ap) {
+        print!("R:{}", address_str.bright_red());
+    } else {
+        print!("R:{}", address_str);
+    }
+
+    match name {
+        Some(name) => print!(" {}       {}", name, value),
+        None => {
+            print!("           {}", value);
+            if ap == ApAddress::Apsel(s32k3xx::SDA_AP_ID) {
+                match s32k3xx::sda_ap_register_name(address as u8) {
+                    Some(name) => print!("                           ({})", name.bright_red()),
+                    None => print!("                           (TODO add reg)"),
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,258 @@
+//! Folding of MEM-AP data register accesses into logical memory transactions
+
+use crate::decoder::{Access, ApAccess, Decoded, MemoryAccess};
+use crate::dp::ApAddress;
+use crate::parser::{AccessRegister, Direction, Response};
+
+/// One or more memory accesses through a MEM-AP to sequential addresses, e.g.
+/// an auto-increment run of DRW accesses or consecutive BDn accesses
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Transaction {
+    pub ap: ApAddress,
+    pub direction: Direction,
+    /// The accesses in order, never empty
+    pub accesses: Vec<MemoryAccess>,
+    pub result: TransactionResult,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum TransactionResult {
+    Ok,
+    /// An AP access of the transaction was answered with FAULT, it ends there
+    Fault,
+}
+
+impl Transaction {
+    fn new(ap: ApAddress, direction: Direction, access: MemoryAccess) -> Self {
+        Self {
+            ap,
+            direction,
+            accesses: vec![access],
+            result: TransactionResult::Ok,
+        }
+    }
+
+    /// Address of the first access
+    pub fn address(&self) -> u32 {
+        self.accesses[0].address
+    }
+
+    /// Access size in bytes, from CSW when known
+    pub fn size(&self) -> Option<u32> {
+        self.accesses[0].size
+    }
+
+    /// Number of accesses
+    pub fn len(&self) -> usize {
+        self.accesses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accesses.is_empty()
+    }
+
+    /// The data word of every access, as transferred through the data register
+    pub fn data(&self) -> impl Iterator<Item = u32> + '_ {
+        self.accesses.iter().map(|access| access.value)
+    }
+
+    /// Whether an access continues this transaction
+    fn continues_with(&self, ap: ApAddress, direction: Direction, access: &MemoryAccess) -> bool {
+        let last = &self.accesses[self.accesses.len() - 1];
+        self.result == TransactionResult::Ok
+            && self.ap == ap
+            && self.direction == direction
+            && last.size == access.size
+            && last
+                .stride
+                .is_some_and(|stride| last.address.wrapping_add(stride) == access.address)
+    }
+}
+
+/// The memory access performed by a decoded operation, if any. Writes perform
+/// the access when issued, reads once their result is returned
+pub fn memory_access(decoded: &Decoded) -> Option<(ApAddress, Direction, MemoryAccess)> {
+    match (decoded.access, decoded.read_result) {
+        (
+            Some(Access::Ap(ApAccess {
+                ap,
+                memory: Some(access),
+                ..
+            })),
+            _,
+        ) => Some((ap, Direction::Write, access)),
+        (
+            _,
+            Some(ApAccess {
+                ap,
+                memory: Some(access),
+                ..
+            }),
+        ) => Some((ap, Direction::Read, access)),
+        _ => None,
+    }
+}
+
+/// Folds decoded operations into memory transactions, a transaction is only
+/// complete once an access that doesn't continue it is seen
+#[derive(Clone, Debug, Default)]
+pub struct TransactionBuilder {
+    current: Option<Transaction>,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next decoded operation, returns the transaction it completed, if any
+    pub fn push(&mut self, decoded: &Decoded) -> Option<Transaction> {
+        let op = &decoded.op;
+        // A FAULT ends the transaction whatever its direction, the sticky flag
+        // makes every later AP access fail until it is cleared
+        if op.access == AccessRegister::AccessPort && op.response == Response::Fault {
+            let mut current = self.current.take()?;
+            current.result = TransactionResult::Fault;
+            return Some(current);
+        }
+
+        let (ap, direction, access) = memory_access(decoded)?;
+
+        match self.current {
+            Some(ref mut current) if current.continues_with(ap, direction, &access) => {
+                current.accesses.push(access);
+                None
+            }
+            _ => self
+                .current
+                .replace(Transaction::new(ap, direction, access)),
+        }
+    }
+
+    /// Complete the pending transaction, e.g. at the end of the log
+    pub fn finish(&mut self) -> Option<Transaction> {
+        self.current.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Decoder;
+    use crate::parser;
+
+    fn transactions(lines: &[&str]) -> Vec<Transaction> {
+        let mut decoder = Decoder::new();
+        let mut builder = TransactionBuilder::new();
+        let mut transactions: Vec<_> = lines
+            .iter()
+            .filter_map(|line| builder.push(&decoder.decode(parser::parse(line).unwrap().1)))
+            .collect();
+        transactions.extend(builder.finish());
+        transactions
+    }
+
+    #[test]
+    fn auto_increment_runs() {
+        let t = transactions(&[
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000052",
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP WR A:3 ACK:1 OK Data:h11111111",
+            "AP WR A:3 ACK:2 Wait",
+            "AP WR A:3 ACK:1 OK Data:h22222222",
+            "AP WR A:3 ACK:1 OK Data:h33333333",
+            // Reading back
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP RD A:3 ACK:1 OK Data:h00000000",
+            "AP RD A:3 ACK:1 OK Data:h11111111",
+            "AP RD A:3 ACK:1 OK Data:h22222222",
+            "DP RD A:3 ACK:1 OK Data:h33333333",
+        ]);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].direction, Direction::Write);
+        assert_eq!(t[0].ap, ApAddress::Apsel(0));
+        assert_eq!(t[0].address(), 0x2000_0000);
+        assert_eq!(t[0].size(), Some(4));
+        assert_eq!(
+            t[0].data().collect::<Vec<_>>(),
+            [0x1111_1111, 0x2222_2222, 0x3333_3333]
+        );
+        assert_eq!(t[1].direction, Direction::Read);
+        assert_eq!(t[1].address(), 0x2000_0000);
+        assert_eq!(
+            t[1].data().collect::<Vec<_>>(),
+            t[0].data().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn banked_data_registers() {
+        let t = transactions(&[
+            "DP WR A:2 ACK:1 OK Data:h04000000",
+            "AP WR A:1 ACK:1 OK Data:hE000EDF0",
+            "DP WR A:2 ACK:1 OK Data:h04000010",
+            "AP RD A:0 ACK:1 OK Data:h00000000",
+            "AP RD A:1 ACK:1 OK Data:h00030003",
+            "AP RD A:2 ACK:1 OK Data:h00000000",
+            "AP RD A:3 ACK:1 OK Data:h00000000",
+            "DP RD A:3 ACK:1 OK Data:h01000000",
+        ]);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].address(), 0xE000_EDF0);
+        assert_eq!(t[0].len(), 4);
+        assert_eq!(t[0].size(), None);
+    }
+
+    #[test]
+    fn no_auto_increment() {
+        // Polling DHCSR
+        let t = transactions(&[
+            "DP WR A:2 ACK:1 OK Data:h04000000",
+            "AP WR A:0 ACK:1 OK Data:h03000002",
+            "AP WR A:1 ACK:1 OK Data:hE000EDF0",
+            "AP RD A:3 ACK:1 OK Data:h00000000",
+            "AP RD A:3 ACK:1 OK Data:h00030003",
+            "DP RD A:3 ACK:1 OK Data:h00030003",
+        ]);
+        assert_eq!(t.len(), 2);
+        assert!(t.iter().all(|t| t.len() == 1 && t.address() == 0xE000_EDF0));
+    }
+
+    #[test]
+    fn fault_ends_transaction() {
+        let t = transactions(&[
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000052",
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP WR A:3 ACK:1 OK Data:h11111111",
+            "AP WR A:3 ACK:4 Fault",
+            "DP WR A:0 ACK:1 OK Data:h0000001E",
+            "AP WR A:3 ACK:1 OK Data:h22222222",
+        ]);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].result, TransactionResult::Fault);
+        assert_eq!(t[0].len(), 1);
+        assert_eq!(t[1].result, TransactionResult::Ok);
+        assert_eq!(t[1].address(), 0x2000_0004);
+    }
+
+    #[test]
+    fn fault_in_other_direction_ends_transaction() {
+        let t = transactions(&[
+            "DP WR A:2 ACK:1 OK Data:h00000000",
+            "AP WR A:0 ACK:1 OK Data:h23000052",
+            "AP WR A:1 ACK:1 OK Data:h20000000",
+            "AP WR A:3 ACK:1 OK Data:h11111111",
+            "AP RD A:3 ACK:4 Fault",
+            "DP WR A:0 ACK:1 OK Data:h0000001E",
+            "AP WR A:3 ACK:1 OK Data:h22222222",
+        ]);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0].direction, Direction::Write);
+        assert_eq!(t[0].result, TransactionResult::Fault);
+        assert_eq!(t[0].len(), 1);
+        assert_eq!(t[1].result, TransactionResult::Ok);
+        assert_eq!(t[1].address(), 0x2000_0004);
+    }
+}
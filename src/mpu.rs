@@ -0,0 +1,418 @@
+//! Reconstruction of the MPU and SAU region configuration
+
+use crate::arm_regs::{
+    format_size, ArchVersion, ArmRegister, MpuCtrl, MpuRasr, MpuRbar, SauCtrl, SauRbar, SauRlar,
+};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The MPU configuration as known at a point of the log
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MpuSnapshot {
+    pub arch: ArchVersion,
+    pub ctrl: Option<MpuCtrl>,
+    /// Regions with a base address or attributes seen, in region order
+    pub regions: Vec<MpuRegion>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MpuRegion {
+    pub number: u8,
+    pub rbar: Option<MpuRbar>,
+    /// MPU_RASR, or MPU_RLAR on Armv8-M
+    pub rasr: Option<MpuRasr>,
+}
+
+impl MpuRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, arch: ArchVersion) -> fmt::Result {
+        write!(f, "region {}: ", self.number)?;
+        match self.rbar {
+            Some(rbar) => write!(f, "{:08X}", rbar.address())?,
+            None => f.write_str("????????")?,
+        }
+        let Some(rasr) = self.rasr else {
+            return f.write_str(" ?");
+        };
+        match arch {
+            ArchVersion::Armv7M => {
+                write!(
+                    f,
+                    " size {} ap:{} xn:{} tex:{} s:{} c:{} b:{}",
+                    format_size(rasr.size_bytes()),
+                    rasr.ap(),
+                    rasr.xn() as u8,
+                    rasr.tex(),
+                    rasr.s() as u8,
+                    rasr.c() as u8,
+                    rasr.b() as u8
+                )?;
+                if rasr.srd() != 0 {
+                    write!(f, " srd:{:02X}", rasr.srd())?;
+                }
+                f.write_str(if rasr.enable() {
+                    " enabled"
+                } else {
+                    " disabled"
+                })
+            }
+            ArchVersion::Armv8M => {
+                write!(f, "..{:08X}", rasr.limit_address())?;
+                if let Some(rbar) = self.rbar {
+                    write!(
+                        f,
+                        " ap:{} sh:{} xn:{}",
+                        rbar.ap(),
+                        rbar.sh(),
+                        rbar.xn() as u8
+                    )?;
+                }
+                write!(f, " attrindx:{}", rasr.attrindx())?;
+                f.write_str(if rasr.en() { " enabled" } else { " disabled" })
+            }
+        }
+    }
+}
+
+impl fmt::Display for MpuSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MPU ")?;
+        match self.ctrl {
+            Some(ctrl) if ctrl.enable() => {
+                f.write_str("enabled")?;
+                if ctrl.privdefena() {
+                    f.write_str(", privdefena")?;
+                }
+                if ctrl.hfnmiena() {
+                    f.write_str(", hfnmiena")?;
+                }
+            }
+            Some(_) => f.write_str("disabled")?,
+            None => f.write_str("?")?,
+        }
+        for region in self.regions.iter() {
+            f.write_str("\n    ")?;
+            region.fmt(f, self.arch)?;
+        }
+        Ok(())
+    }
+}
+
+/// The SAU configuration as known at a point of the log
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SauSnapshot {
+    pub ctrl: Option<SauCtrl>,
+    /// Regions with a base or limit address seen, in region order
+    pub regions: Vec<SauRegion>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SauRegion {
+    pub number: u8,
+    pub rbar: Option<SauRbar>,
+    pub rlar: Option<SauRlar>,
+}
+
+impl fmt::Display for SauRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "region {}: ", self.number)?;
+        match self.rbar {
+            Some(rbar) => write!(f, "{:08X}", rbar.address())?,
+            None => f.write_str("????????")?,
+        }
+        let Some(rlar) = self.rlar else {
+            return f.write_str(" ?");
+        };
+        write!(f, "..{:08X}", rlar.limit_address())?;
+        f.write_str(if rlar.nsc() { " NSC" } else { " NS" })?;
+        f.write_str(if rlar.enable() {
+            " enabled"
+        } else {
+            " disabled"
+        })
+    }
+}
+
+impl fmt::Display for SauSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SAU ")?;
+        match self.ctrl {
+            Some(ctrl) if ctrl.enable() => f.write_str("enabled")?,
+            Some(ctrl) if ctrl.allns() => f.write_str("disabled, all Non-secure")?,
+            Some(_) => f.write_str("disabled, all Secure")?,
+            None => f.write_str("?")?,
+        }
+        for region in self.regions.iter() {
+            write!(f, "\n    {}", region)?;
+        }
+        Ok(())
+    }
+}
+
+/// A write to MPU_CTRL or SAU_CTRL, typically the last step of programming
+/// the regions, with the configuration it completed
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum MpuEvent {
+    Mpu(MpuSnapshot),
+    Sau(SauSnapshot),
+}
+
+impl fmt::Display for MpuEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MpuEvent::Mpu(snapshot) => write!(f, "{}", snapshot),
+            MpuEvent::Sau(snapshot) => write!(f, "{}", snapshot),
+        }
+    }
+}
+
+/// MPU and SAU state of one MEM-AP
+#[derive(Clone, Debug, Default)]
+struct Protection {
+    /// Known once CPUID was read or an Armv8-M only register accessed
+    arch: Option<ArchVersion>,
+    mpu_ctrl: Option<MpuCtrl>,
+    mpu_rnr: u8,
+    mpu_regions: BTreeMap<u8, (Option<MpuRbar>, Option<MpuRasr>)>,
+    sau_ctrl: Option<SauCtrl>,
+    sau_rnr: u8,
+    sau_regions: BTreeMap<u8, (Option<SauRbar>, Option<SauRlar>)>,
+}
+
+impl Protection {
+    fn arch(&self) -> ArchVersion {
+        self.arch.unwrap_or(ArchVersion::Armv7M)
+    }
+
+    /// The region accessed through an MPU_RBAR or MPU_RASR/MPU_RLAR alias.
+    /// Armv7-M aliases all access the MPU_RNR region, Armv8-M ones the
+    /// regions following it
+    fn mpu_region(&self, alias: u8) -> u8 {
+        match self.arch() {
+            ArchVersion::Armv7M => self.mpu_rnr,
+            ArchVersion::Armv8M => (self.mpu_rnr & !0x3) + alias,
+        }
+    }
+
+    fn mpu_snapshot(&self) -> MpuSnapshot {
+        MpuSnapshot {
+            arch: self.arch(),
+            ctrl: self.mpu_ctrl,
+            regions: self
+                .mpu_regions
+                .iter()
+                .map(|(&number, &(rbar, rasr))| MpuRegion { number, rbar, rasr })
+                .collect(),
+        }
+    }
+
+    fn sau_snapshot(&self) -> SauSnapshot {
+        SauSnapshot {
+            ctrl: self.sau_ctrl,
+            regions: self
+                .sau_regions
+                .iter()
+                .map(|(&number, &(rbar, rlar))| SauRegion { number, rbar, rlar })
+                .collect(),
+        }
+    }
+
+    fn has_mpu(&self) -> bool {
+        self.mpu_ctrl.is_some() || !self.mpu_regions.is_empty()
+    }
+
+    fn has_sau(&self) -> bool {
+        self.sau_ctrl.is_some() || !self.sau_regions.is_empty()
+    }
+}
+
+/// Tracks MPU and SAU register accesses per MEM-AP. The MPU registers are
+/// taken in the Armv7-M layout unless CPUID was read or MPU_MAIRn or the SAU
+/// accessed
+#[derive(Clone, Debug, Default)]
+pub struct MpuTracker {
+    aps: BTreeMap<ApAddress, Protection>,
+}
+
+impl MpuTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the configuration completed by a
+    /// control register write, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<MpuEvent> {
+        let protection = self.aps.entry(ap).or_default();
+        match access.register? {
+            ArmRegister::Cpuid(cpuid) if direction == Direction::Read => {
+                protection.arch = cpuid.arch_version().or(protection.arch);
+            }
+            ArmRegister::MpuCtrl(ctrl) => {
+                protection.mpu_ctrl = Some(ctrl);
+                if direction == Direction::Write {
+                    return Some(MpuEvent::Mpu(protection.mpu_snapshot()));
+                }
+            }
+            ArmRegister::MpuRnr(rnr) => protection.mpu_rnr = rnr.region(),
+            ArmRegister::MpuRbar(alias, rbar) => {
+                // An Armv7-M write with VALID set selects the region first
+                if protection.arch() == ArchVersion::Armv7M
+                    && direction == Direction::Write
+                    && rbar.valid()
+                {
+                    protection.mpu_rnr = rbar.region();
+                }
+                let region = protection.mpu_region(alias);
+                protection.mpu_regions.entry(region).or_default().0 = Some(rbar);
+            }
+            ArmRegister::MpuRasr(alias, rasr) => {
+                let region = protection.mpu_region(alias);
+                protection.mpu_regions.entry(region).or_default().1 = Some(rasr);
+            }
+            ArmRegister::MpuMair(..) => protection.arch = Some(ArchVersion::Armv8M),
+            ArmRegister::SauCtrl(ctrl) => {
+                protection.arch = Some(ArchVersion::Armv8M);
+                protection.sau_ctrl = Some(ctrl);
+                if direction == Direction::Write {
+                    return Some(MpuEvent::Sau(protection.sau_snapshot()));
+                }
+            }
+            ArmRegister::SauRnr(rnr) => {
+                protection.arch = Some(ArchVersion::Armv8M);
+                protection.sau_rnr = rnr.region();
+            }
+            ArmRegister::SauRbar(rbar) => {
+                protection.arch = Some(ArchVersion::Armv8M);
+                let region = protection.sau_rnr;
+                protection.sau_regions.entry(region).or_default().0 = Some(rbar);
+            }
+            ArmRegister::SauRlar(rlar) => {
+                protection.arch = Some(ArchVersion::Armv8M);
+                let region = protection.sau_rnr;
+                protection.sau_regions.entry(region).or_default().1 = Some(rlar);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// The MPU configuration of an AP as known now, if any MPU register was
+    /// accessed
+    pub fn mpu_snapshot(&self, ap: ApAddress) -> Option<MpuSnapshot> {
+        let protection = self.aps.get(&ap).filter(|p| p.has_mpu())?;
+        Some(protection.mpu_snapshot())
+    }
+
+    /// The SAU configuration of an AP as known now, if any SAU register was
+    /// accessed
+    pub fn sau_snapshot(&self, ap: ApAddress) -> Option<SauSnapshot> {
+        let protection = self.aps.get(&ap).filter(|p| p.has_sau())?;
+        Some(protection.sau_snapshot())
+    }
+
+    /// The APs through which MPU or SAU registers were accessed
+    pub fn aps(&self) -> impl Iterator<Item = ApAddress> + '_ {
+        self.aps
+            .iter()
+            .filter(|(_, p)| p.has_mpu() || p.has_sau())
+            .map(|(&ap, _)| ap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::track;
+
+    #[test]
+    fn armv7m_regions() {
+        let mut tracker = MpuTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000012",
+                // MPU_CTRL disabled
+                "AP WR A:1 ACK:1 OK Data:hE000ED94",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+                // MPU_RBAR with VALID for region 1, then MPU_RASR 64KiB
+                "AP WR A:1 ACK:1 OK Data:hE000ED9C",
+                "AP WR A:3 ACK:1 OK Data:h20000011",
+                "AP WR A:3 ACK:1 OK Data:h0306001F",
+                // MPU_RNR 2 and MPU_RBAR_A1/MPU_RASR_A1 without VALID
+                "AP WR A:1 ACK:1 OK Data:hE000ED98",
+                "AP WR A:3 ACK:1 OK Data:h00000002",
+                "AP WR A:1 ACK:1 OK Data:hE000EDA4",
+                "AP WR A:3 ACK:1 OK Data:h08000000",
+                "AP WR A:3 ACK:1 OK Data:h13020027",
+                // MPU_CTRL enabled
+                "AP WR A:1 ACK:1 OK Data:hE000ED94",
+                "AP WR A:3 ACK:1 OK Data:h00000005",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].to_string(), "MPU disabled");
+        assert_eq!(
+            events[1].to_string(),
+            "MPU enabled, privdefena\n    \
+             region 1: 20000000 size 64KiB ap:3 xn:0 tex:0 s:1 c:1 b:0 enabled\n    \
+             region 2: 08000000 size 1MiB ap:3 xn:1 tex:0 s:0 c:1 b:0 enabled"
+        );
+        assert_eq!(
+            tracker
+                .mpu_snapshot(ApAddress::Apsel(0))
+                .unwrap()
+                .regions
+                .len(),
+            2
+        );
+        assert!(tracker.sau_snapshot(ApAddress::Apsel(0)).is_none());
+    }
+
+    #[test]
+    fn armv8m_regions() {
+        let mut tracker = MpuTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000012",
+                // CPUID Cortex-M33
+                "AP WR A:1 ACK:1 OK Data:hE000ED00",
+                "AP RD A:3 ACK:1 OK Data:h00000000",
+                "DP RD A:3 ACK:1 OK Data:h410FD214",
+                // MPU_RNR 4, MPU_RBAR_A1/MPU_RLAR_A1 give region 5
+                "AP WR A:1 ACK:1 OK Data:hE000ED98",
+                "AP WR A:3 ACK:1 OK Data:h00000004",
+                "AP WR A:1 ACK:1 OK Data:hE000EDA4",
+                "AP WR A:3 ACK:1 OK Data:h20000003",
+                "AP WR A:3 ACK:1 OK Data:h2000FFE3",
+                // SAU_RNR, SAU_RBAR, SAU_RLAR, SAU_CTRL
+                "AP WR A:1 ACK:1 OK Data:hE000EDD8",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+                "AP WR A:3 ACK:1 OK Data:h10000000",
+                "AP WR A:3 ACK:1 OK Data:h1000FFE3",
+                "AP WR A:1 ACK:1 OK Data:hE000EDD0",
+                "AP WR A:3 ACK:1 OK Data:h00000001",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(
+            events.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["SAU enabled\n    region 0: 10000000..1000FFFF NSC enabled"]
+        );
+        assert_eq!(
+            tracker
+                .mpu_snapshot(ApAddress::Apsel(0))
+                .unwrap()
+                .to_string(),
+            "MPU ?\n    region 5: 20000000..2000FFFF ap:1 sh:0 xn:1 attrindx:1 enabled"
+        );
+    }
+}
@@ -1,11 +1,145 @@
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{digit1, hex_digit1, space0},
-    combinator::{map, map_res, value},
-    sequence::{preceded, tuple},
+    bytes::complete::{tag, tag_no_case, take_while_m_n},
+    character::complete::{char, digit1, hex_digit1, not_line_ending, space0, space1},
+    combinator::{eof, map, map_res, opt, value},
+    sequence::{pair, preceded, terminated, tuple},
     IResult,
 };
+use std::fmt;
+use std::time::Duration;
+
+/// Stateful log parser, tracks the current capture block so that every
+/// operation can be attributed to the block header preceding it
+#[derive(Clone, Debug, Default)]
+pub struct LogParser {
+    block: Option<Block>,
+    line_number: usize,
+}
+
+impl LogParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recently seen capture block, if any
+    pub fn block(&self) -> Option<Block> {
+        self.block
+    }
+
+    /// One-based number of the last line given to [`LogParser::parse_line`]
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Parse a single log line, returns `None` for lines that aren't recognised
+    pub fn parse_line(&mut self, s: &str) -> Option<Line> {
+        self.line_number += 1;
+        let (_, mut line) = line(s).ok()?;
+        match line {
+            Line::Block(ref mut block) => {
+                block.index = self.block.map(|b| b.index + 1).unwrap_or(0);
+                self.block = Some(*block);
+            }
+            Line::Operation(ref mut op) => op.block = self.block,
+            Line::Sequence(_) => (),
+        }
+        Some(line)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Line {
+    /// A capture block header
+    Block(Block),
+    Operation(SwdOperation),
+    /// A non-packet bit sequence, e.g. a line reset
+    Sequence(Sequence),
+}
+
+/// Non-packet SWD line sequences used to connect to, or switch, the wire protocol
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Sequence {
+    /// At least 50 clock cycles with SWDIO high
+    LineReset,
+    /// The 16-bit 0xE79E JTAG-to-SWD select sequence
+    JtagToSwd,
+    /// The 16-bit 0xE3BC SWD-to-dormant select sequence
+    SwdToDormant,
+    /// The 128-bit dormant state selection alert
+    SelectionAlert,
+    /// The activation code following a selection alert, 0x1A selects SWD
+    Activation(u8),
+}
+
+impl Sequence {
+    /// Activation code selecting the SW-DP
+    pub const SWD_ACTIVATION_CODE: u8 = 0x1A;
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sequence::LineReset => f.write_str("LINE RESET"),
+            Sequence::JtagToSwd => f.write_str("JTAG-TO-SWD"),
+            Sequence::SwdToDormant => f.write_str("SWD-TO-DORMANT"),
+            Sequence::SelectionAlert => f.write_str("SELECTION ALERT"),
+            Sequence::Activation(code) => write!(f, "ACTIVATION CODE {:02X}", code),
+        }
+    }
+}
+
+/// A capture block, WaveForms starts each one with a timestamp header line
+/// (e.g. `03:57:59.988:`)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Block {
+    /// Zero-based index of the block within the log
+    pub index: usize,
+    pub timestamp: Timestamp,
+}
+
+/// Wall-clock time of day of a capture block, millisecond resolution
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Timestamp {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub millis: u16,
+}
+
+impl Timestamp {
+    pub fn since_midnight(&self) -> Duration {
+        Duration::from_millis(
+            (((u64::from(self.hours) * 60) + u64::from(self.minutes)) * 60
+                + u64::from(self.seconds))
+                * 1000
+                + u64::from(self.millis),
+        )
+    }
+
+    /// Time elapsed since an earlier timestamp, accounts for a capture
+    /// running past midnight
+    pub fn duration_since(&self, earlier: Timestamp) -> Duration {
+        const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+        let now = self.since_midnight();
+        let earlier = earlier.since_midnight();
+        if now >= earlier {
+            now - earlier
+        } else {
+            (now + DAY) - earlier
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}.{:03}",
+            self.hours, self.minutes, self.seconds, self.millis
+        )
+    }
+}
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct SwdOperation {
@@ -13,7 +147,50 @@ pub struct SwdOperation {
     pub direction: Direction,
     pub address_2_3: u8,
     pub ack: u8,
-    pub data: u32,
+    pub response: Response,
+    /// The capture block this operation belongs to, only known when parsed
+    /// with a [`LogParser`]
+    pub block: Option<Block>,
+}
+
+impl SwdOperation {
+    /// The transferred data word, only available for OK acknowledged operations
+    /// with a valid data phase
+    pub fn data(&self) -> Option<u32> {
+        match self.response {
+            Response::Ok(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// The target's response to an operation request
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Response {
+    /// OK acknowledge with the data phase word
+    Ok(u32),
+    /// OK acknowledge, but the data phase parity check failed
+    ParityError(u32),
+    /// WAIT acknowledge, the operation must be retried
+    Wait,
+    /// FAULT acknowledge, a sticky error flag is set
+    Fault,
+    /// No valid acknowledge (protocol error), e.g. the target did not drive the line.
+    /// Holds the data phase word when one was captured anyway, as for
+    /// TARGETSEL writes whose ACK is ignored
+    NoAck(Option<u32>),
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Response::Ok(_) => f.write_str("OK"),
+            Response::ParityError(_) => f.write_str("PARITY ERROR"),
+            Response::Wait => f.write_str("WAIT"),
+            Response::Fault => f.write_str("FAULT"),
+            Response::NoAck(_) => f.write_str("NO ACK"),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -28,23 +205,117 @@ pub enum Direction {
     Read,
 }
 
-pub fn parse(s: &str) -> IResult<&str, SwdOperation> {
+pub fn line(s: &str) -> IResult<&str, Line> {
+    alt((
+        map(block, Line::Block),
+        map(parse, Line::Operation),
+        map(sequence, Line::Sequence),
+    ))(s)
+}
+
+/// Parse a non-packet sequence line: `Line Reset`, `JTAG to SWD` (or
+/// `hE79E`), `SWD to Dormant` (or `hE3BC`), `Selection Alert` or
+/// `Activation code h1A`. The whole line has to match, ignoring case and
+/// surrounding whitespace, words may be separated by a space or a hyphen
+pub fn sequence(s: &str) -> IResult<&str, Sequence> {
+    terminated(
+        preceded(
+            space0,
+            alt((
+                value(Sequence::LineReset, words(&["Line", "Reset"])),
+                value(
+                    Sequence::JtagToSwd,
+                    alt((words(&["JTAG", "to", "SWD"]), words(&["hE79E"]))),
+                ),
+                value(
+                    Sequence::SwdToDormant,
+                    alt((words(&["SWD", "to", "Dormant"]), words(&["hE3BC"]))),
+                ),
+                value(Sequence::SelectionAlert, words(&["Selection", "Alert"])),
+                map(
+                    preceded(
+                        pair(words(&["Activation", "code"]), space1),
+                        preceded(
+                            alt((tag_no_case("0x"), tag_no_case("h"))),
+                            map_res(
+                                take_while_m_n(1, 2, |c: char| c.is_ascii_hexdigit()),
+                                |hex| u8::from_str_radix(hex, 16),
+                            ),
+                        ),
+                    ),
+                    Sequence::Activation,
+                ),
+            )),
+        ),
+        line_end,
+    )(s)
+}
+
+/// Case-insensitive words separated by a space or a hyphen
+fn words<'a>(words: &'static [&'static str]) -> impl FnMut(&'a str) -> IResult<&'a str, ()> {
+    move |mut s| {
+        for (i, word) in words.iter().enumerate() {
+            if i > 0 {
+                (s, _) = alt((tag("-"), space1))(s)?;
+            }
+            (s, _) = tag_no_case(*word)(s)?;
+        }
+        Ok((s, ()))
+    }
+}
+
+/// Parse a block header, the block index is left at zero
+pub fn block(s: &str) -> IResult<&str, Block> {
+    map(
+        terminated(preceded(space0, timestamp), char(':')),
+        |timestamp| Block {
+            index: 0,
+            timestamp,
+        },
+    )(s)
+}
+
+pub fn timestamp(s: &str) -> IResult<&str, Timestamp> {
     map(
         tuple((
-            preceded(space0, access),
-            preceded(space0, direction),
-            preceded(space0, address_2_3),
-            preceded(space0, ack),
-            preceded(space0, ok_respose_data),
+            terminated(decimal(2), char(':')),
+            terminated(decimal(2), char(':')),
+            terminated(decimal(2), char('.')),
+            decimal(3),
         )),
-        |(access, direction, address_2_3, ack, data)| SwdOperation {
+        |(hours, minutes, seconds, millis)| Timestamp {
+            hours: hours as u8,
+            minutes: minutes as u8,
+            seconds: seconds as u8,
+            millis,
+        },
+    )(s)
+}
+
+fn decimal<'a>(digits: usize) -> impl FnMut(&'a str) -> IResult<&'a str, u16> {
+    map_res(
+        take_while_m_n(digits, digits, |c: char| c.is_ascii_digit()),
+        str::parse,
+    )
+}
+
+pub fn parse(s: &str) -> IResult<&str, SwdOperation> {
+    let (s, (access, direction, address_2_3, ack)) = tuple((
+        preceded(space0, access),
+        preceded(space0, direction),
+        preceded(space0, address_2_3),
+        preceded(space0, ack),
+    ))(s)?;
+    map(preceded(space0, response(ack)), move |response| {
+        SwdOperation {
             access,
             direction,
             address_2_3,
             ack,
-            data,
-        },
-    )(s)
+            response,
+            block: None,
+        }
+    })(s)
 }
 
 fn access(s: &str) -> IResult<&str, AccessRegister> {
@@ -69,10 +340,43 @@ fn ack(s: &str) -> IResult<&str, u8> {
     preceded(tag("ACK:"), map_res(digit1, str::parse))(s)
 }
 
-fn ok_respose_data(s: &str) -> IResult<&str, u32> {
-    preceded(
-        tag("OK Data:h"),
-        map_res(hex_digit1, |out: &str| u32::from_str_radix(out, 16)),
+/// The response following an ACK value, the OK, WAIT and FAULT responses
+/// have to match their ACK and end the line
+fn response<'a>(ack: u8) -> impl FnMut(&'a str) -> IResult<&'a str, Response> {
+    move |s| match ack {
+        1 => terminated(ok_respose_data, line_end)(s),
+        2 => value(Response::Wait, terminated(tag("Wait"), line_end))(s),
+        4 => value(Response::Fault, terminated(tag("Fault"), line_end))(s),
+        // Anything else is a protocol error (no or invalid ACK)
+        _ => map(not_line_ending, |rest| Response::NoAck(no_ack_data(rest)))(s),
+    }
+}
+
+/// Trailing whitespace up to the end of the line
+fn line_end(s: &str) -> IResult<&str, ()> {
+    value((), pair(space0, eof))(s)
+}
+
+/// Data phase word following an invalid ACK, e.g. `ACK:7 Data:h01002927`
+fn no_ack_data(s: &str) -> Option<u32> {
+    let (_, data) = s.split_once("Data:h")?;
+    let (_, data) = hex_digit1::<_, nom::error::Error<&str>>(data).ok()?;
+    u32::from_str_radix(data, 16).ok()
+}
+
+fn ok_respose_data(s: &str) -> IResult<&str, Response> {
+    map(
+        pair(
+            preceded(
+                tag("OK Data:h"),
+                map_res(hex_digit1, |out: &str| u32::from_str_radix(out, 16)),
+            ),
+            opt(preceded(space0, tag_no_case("Parity error"))),
+        ),
+        |(data, parity_error)| match parity_error {
+            Some(_) => Response::ParityError(data),
+            None => Response::Ok(data),
+        },
     )(s)
 }
 
@@ -109,7 +413,49 @@ mod tests {
 
     #[test]
     fn parse_response_data() {
-        assert_eq!(ok_respose_data("OK Data:h00030003"), Ok(("", 0x0003_0003)));
+        assert_eq!(
+            ok_respose_data("OK Data:h00030003"),
+            Ok(("", Response::Ok(0x0003_0003)))
+        );
+        assert_eq!(
+            ok_respose_data("OK Data:h00030003 Parity error"),
+            Ok(("", Response::ParityError(0x0003_0003)))
+        );
+    }
+
+    #[test]
+    fn parse_response() {
+        assert_eq!(
+            response(1)("OK Data:h00030003"),
+            Ok(("", Response::Ok(0x0003_0003)))
+        );
+        assert_eq!(response(2)("Wait"), Ok(("", Response::Wait)));
+        assert_eq!(response(4)("Fault"), Ok(("", Response::Fault)));
+        assert_eq!(response(0)(""), Ok(("", Response::NoAck(None))));
+        assert_eq!(response(7)("Error"), Ok(("", Response::NoAck(None))));
+        assert_eq!(
+            response(7)("Data:h01002927"),
+            Ok(("", Response::NoAck(Some(0x0100_2927))))
+        );
+        assert_eq!(
+            response(7)("Error Data:h01002927"),
+            Ok(("", Response::NoAck(Some(0x0100_2927))))
+        );
+    }
+
+    #[test]
+    fn reject_malformed_response() {
+        // The response has to end the line
+        assert!(response(2)("Waiting").is_err());
+        assert!(response(4)("Faulty").is_err());
+        assert!(response(1)("OK Data:h00030003 Error").is_err());
+        // A malformed data phase isn't taken as a protocol error
+        assert!(response(1)("OK Data:hZZ").is_err());
+        assert!(response(1)("OK").is_err());
+        // The response has to match the ACK
+        assert!(response(1)("Wait").is_err());
+        assert!(response(2)("Fault").is_err());
+        assert!(response(4)("OK Data:h00030003").is_err());
     }
 
     #[test]
@@ -123,9 +469,196 @@ mod tests {
                     direction: Direction::Read,
                     address_2_3: 1,
                     ack: 1,
-                    data: 0x61000003,
+                    response: Response::Ok(0x61000003),
+                    block: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_wait_op() {
+        assert_eq!(
+            parse("DP WR A:2 ACK:2 Wait"),
+            Ok((
+                "",
+                SwdOperation {
+                    access: AccessRegister::DebugPort,
+                    direction: Direction::Write,
+                    address_2_3: 2,
+                    ack: 2,
+                    response: Response::Wait,
+                    block: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_fault_op() {
+        assert_eq!(
+            parse("AP RD A:3 ACK:4 Fault"),
+            Ok((
+                "",
+                SwdOperation {
+                    access: AccessRegister::AccessPort,
+                    direction: Direction::Read,
+                    address_2_3: 3,
+                    ack: 4,
+                    response: Response::Fault,
+                    block: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn reject_malformed_op() {
+        assert!(parse("AP RD A:3 ACK:1 OK Data:hZZ").is_err());
+        assert!(parse("DP WR A:2 ACK:2 Waiting").is_err());
+        assert!(parse("AP RD A:3 ACK:2 Fault").is_err());
+    }
+
+    #[test]
+    fn parse_no_ack_op() {
+        assert_eq!(
+            parse("DP RD A:0 ACK:7"),
+            Ok((
+                "",
+                SwdOperation {
+                    access: AccessRegister::DebugPort,
+                    direction: Direction::Read,
+                    address_2_3: 0,
+                    ack: 7,
+                    response: Response::NoAck(None),
+                    block: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_targetsel_op() {
+        let (_, op) = parse("DP WR A:3 ACK:7 Data:h01002927").unwrap();
+        assert_eq!(op.direction, Direction::Write);
+        assert_eq!(op.address_2_3, 3);
+        assert_eq!(op.ack, 7);
+        assert_eq!(op.response, Response::NoAck(Some(0x0100_2927)));
+        assert_eq!(op.data(), None);
+    }
+
+    #[test]
+    fn parse_sequence() {
+        assert_eq!(sequence("Line Reset"), Ok(("", Sequence::LineReset)));
+        assert_eq!(sequence("  LINE RESET "), Ok(("", Sequence::LineReset)));
+        assert_eq!(sequence("JTAG-to-SWD"), Ok(("", Sequence::JtagToSwd)));
+        assert_eq!(sequence("JTAG to SWD"), Ok(("", Sequence::JtagToSwd)));
+        assert_eq!(sequence("hE79E"), Ok(("", Sequence::JtagToSwd)));
+        assert_eq!(sequence("SWD to dormant"), Ok(("", Sequence::SwdToDormant)));
+        assert_eq!(sequence("hE3BC"), Ok(("", Sequence::SwdToDormant)));
+        assert_eq!(
+            sequence("Selection Alert"),
+            Ok(("", Sequence::SelectionAlert))
+        );
+        assert_eq!(
+            sequence("Activation code h1A"),
+            Ok(("", Sequence::Activation(0x1A)))
+        );
+        assert_eq!(
+            sequence("Activation code 0x0A"),
+            Ok(("", Sequence::Activation(0x0A)))
+        );
+        assert!(sequence("garbage").is_err());
+        assert!(sequence("").is_err());
+    }
+
+    #[test]
+    fn reject_sequence_lookalikes() {
+        // The activation code is required
+        assert!(sequence("Activation code").is_err());
+        assert!(sequence("Activation code hZZ").is_err());
+        // Free text mentioning a sequence
+        assert!(sequence("Target reset by the debugger").is_err());
+        assert!(sequence("RESET").is_err());
+        assert!(sequence("Line Reset done").is_err());
+        assert!(sequence("# the probe sends a selection alert").is_err());
+        assert!(sequence("Data:hE79E").is_err());
+        assert!(sequence("AP WR A:3 ACK:1 OK Data:hE3BC0000").is_err());
+        let mut p = LogParser::new();
+        assert_eq!(p.parse_line("WaveForms reset the device"), None);
+    }
+
+    #[test]
+    fn log_parser_sequences() {
+        let mut p = LogParser::new();
+        assert_eq!(
+            p.parse_line("Line reset"),
+            Some(Line::Sequence(Sequence::LineReset))
+        );
+        // Packets still take precedence
+        assert!(matches!(
+            p.parse_line("DP RD A:0 ACK:1 OK Data:h6BA02477"),
+            Some(Line::Operation(_))
+        ));
+    }
+
+    #[test]
+    fn parse_timestamp() {
+        assert_eq!(
+            timestamp("03:57:59.988"),
+            Ok((
+                "",
+                Timestamp {
+                    hours: 3,
+                    minutes: 57,
+                    seconds: 59,
+                    millis: 988,
                 }
             ))
         );
+        assert!(timestamp("3:57:59.988").is_err());
+    }
+
+    #[test]
+    fn parse_block() {
+        let (rest, b) = block("03:57:59.988:").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(b.index, 0);
+        assert_eq!(b.timestamp.to_string(), "03:57:59.988");
+    }
+
+    #[test]
+    fn timestamp_duration_since() {
+        let t0 = timestamp("03:57:59.988").unwrap().1;
+        let t1 = timestamp("03:58:00.010").unwrap().1;
+        assert_eq!(t1.duration_since(t0), Duration::from_millis(22));
+        let t2 = timestamp("00:00:00.001").unwrap().1;
+        let t3 = timestamp("23:59:59.999").unwrap().1;
+        assert_eq!(t2.duration_since(t3), Duration::from_millis(2));
+    }
+
+    #[test]
+    fn log_parser_attaches_blocks() {
+        let mut p = LogParser::new();
+        assert!(p.parse_line("AP RD A:1 ACK:1 OK Data:h61000003").is_some());
+        assert_eq!(p.block(), None);
+        assert!(matches!(
+            p.parse_line("03:57:59.988:"),
+            Some(Line::Block(Block { index: 0, .. }))
+        ));
+        let Some(Line::Operation(op)) = p.parse_line("DP WR A:2 ACK:2 Wait") else {
+            panic!("Expected an operation");
+        };
+        assert_eq!(op.block, p.block());
+        assert!(matches!(
+            p.parse_line("03:58:00.010:"),
+            Some(Line::Block(Block { index: 1, .. }))
+        ));
+        let Some(Line::Operation(op)) = p.parse_line("DP WR A:2 ACK:1 OK Data:h04000010") else {
+            panic!("Expected an operation");
+        };
+        assert_eq!(op.block.map(|b| b.index), Some(1));
+        assert_eq!(p.parse_line("garbage"), None);
+        assert_eq!(p.line_number(), 6);
     }
 }
@@ -0,0 +1,33 @@
+//! NXP S32K3xx (S32K344) specifics
+
+pub const APB_AP_ID: u8 = 1;
+pub const CM7_0_AHB_AP_ID: u8 = 4;
+pub const MDM_AP_ID: u8 = 6;
+pub const SDA_AP_ID: u8 = 7;
+pub const AP_IDS: [u8; 4] = [APB_AP_ID, CM7_0_AHB_AP_ID, MDM_AP_ID, SDA_AP_ID];
+
+/// Name of a known S32K3xx AP
+pub fn ap_name(apsel: u8) -> Option<&'static str> {
+    Some(match apsel {
+        APB_AP_ID => "APB_AP",
+        CM7_0_AHB_AP_ID => "CM7_0_AHB_AP",
+        MDM_AP_ID => "MDM_AP",
+        SDA_AP_ID => "SDA_AP",
+        _ => return None,
+    })
+}
+
+/// The MDM-AP and SDA-AP are vendor specific, not MEM-APs
+pub fn is_vendor_ap(apsel: u8) -> bool {
+    apsel == MDM_AP_ID || apsel == SDA_AP_ID
+}
+
+/// Name of a known SDA-AP register
+pub fn sda_ap_register_name(address: u8) -> Option<&'static str> {
+    Some(match address {
+        0x80 => "DBGENCTRL",
+        0x90 => "SDAAPRSTCTRL",
+        0xFC => "ID",
+        _ => return None,
+    })
+}
@@ -0,0 +1,182 @@
+//! SWO trace configuration through the ITM and TPIU
+
+use crate::arm_regs::{ArmRegister, ItmTer, PinProtocol};
+use crate::decoder::MemoryAccess;
+use crate::dp::ApAddress;
+use crate::parser::Direction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The SWO setup as known when the ITM was enabled
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct SwoConfiguration {
+    /// From TPIU_SPPR
+    pub protocol: Option<PinProtocol>,
+    /// Reference clock divisor, from TPIU_ACPR
+    pub prescaler: Option<u32>,
+    /// Continuous formatting, from TPIU_FFCR
+    pub formatter: Option<bool>,
+    /// ITM_TERn values, by register number
+    pub stimulus_ports: [Option<u32>; ItmTer::COUNT as usize],
+}
+
+impl SwoConfiguration {
+    /// The enabled stimulus ports, `None` if no ITM_TERn was accessed
+    pub fn enabled_ports(&self) -> Option<Vec<u32>> {
+        if self.stimulus_ports.iter().all(Option::is_none) {
+            return None;
+        }
+        let ports = self.stimulus_ports.iter().enumerate().flat_map(|(n, ter)| {
+            let ter = ter.unwrap_or(0);
+            (0..32)
+                .filter(move |bit| ter & (1 << bit) != 0)
+                .map(move |bit| n as u32 * 32 + bit)
+        });
+        Some(ports.collect())
+    }
+}
+
+impl fmt::Display for SwoConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.protocol {
+            Some(protocol) => write!(f, "{}", protocol)?,
+            None => f.write_str("protocol ?")?,
+        }
+        match self.prescaler {
+            Some(prescaler) => write!(f, ", prescaler {}", prescaler)?,
+            None => f.write_str(", prescaler ?")?,
+        }
+        match self.formatter {
+            Some(true) => f.write_str(", formatter on")?,
+            Some(false) => f.write_str(", formatter off")?,
+            None => {}
+        }
+        f.write_str(", stimulus ports ")?;
+        let Some(ports) = self.enabled_ports() else {
+            return f.write_str("?");
+        };
+        if ports.is_empty() {
+            return f.write_str("none");
+        }
+        // Runs of consecutive ports as ranges
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for port in ports {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == port => *last = port,
+                _ => runs.push((port, port)),
+            }
+        }
+        for (i, (first, last)) in runs.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if first == last {
+                write!(f, "{}", first)?;
+            } else {
+                write!(f, "{}-{}", first, last)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SwoEvent {
+    /// ITM_TCR was written enabling the ITM
+    Configured(SwoConfiguration),
+    /// ITM_TCR was written disabling the ITM
+    ItmDisabled,
+}
+
+impl fmt::Display for SwoEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwoEvent::Configured(configuration) => write!(f, "SWO configured: {}", configuration),
+            SwoEvent::ItmDisabled => f.write_str("ITM disabled"),
+        }
+    }
+}
+
+/// Tracks the ITM and TPIU configuration per MEM-AP, reporting it once the
+/// ITM gets enabled
+#[derive(Clone, Debug, Default)]
+pub struct SwoTracker {
+    configurations: BTreeMap<ApAddress, SwoConfiguration>,
+}
+
+impl SwoTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next memory access, returns the event it caused, if any
+    pub fn push(
+        &mut self,
+        ap: ApAddress,
+        direction: Direction,
+        access: &MemoryAccess,
+    ) -> Option<SwoEvent> {
+        let configuration = self.configurations.entry(ap).or_default();
+        match access.register? {
+            ArmRegister::ItmTer(n, ter) => configuration.stimulus_ports[n as usize] = Some(ter.0),
+            ArmRegister::TpiuAcpr(acpr) => configuration.prescaler = Some(acpr.prescaler()),
+            ArmRegister::TpiuSppr(sppr) => configuration.protocol = sppr.protocol(),
+            ArmRegister::TpiuFfcr(ffcr) => configuration.formatter = Some(ffcr.enfcont()),
+            ArmRegister::ItmTcr(tcr) if direction == Direction::Write => {
+                return Some(if tcr.itmena() {
+                    SwoEvent::Configured(*configuration)
+                } else {
+                    SwoEvent::ItmDisabled
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::track;
+
+    #[test]
+    fn swo_setup() {
+        let mut tracker = SwoTracker::new();
+        let events = track(
+            &[
+                "DP WR A:2 ACK:1 OK Data:h00000000",
+                "AP WR A:0 ACK:1 OK Data:h23000002",
+                // TPIU_SPPR NRZ, TPIU_ACPR, TPIU_FFCR
+                "AP WR A:1 ACK:1 OK Data:hE00400F0",
+                "AP WR A:3 ACK:1 OK Data:h00000002",
+                "AP WR A:1 ACK:1 OK Data:hE0040010",
+                "AP WR A:3 ACK:1 OK Data:h0000002F",
+                "AP WR A:1 ACK:1 OK Data:hE0040304",
+                "AP WR A:3 ACK:1 OK Data:h00000100",
+                // ITM_LAR, ITM_TCR disabled, ITM_TER0, ITM_TCR
+                "AP WR A:1 ACK:1 OK Data:hE0000FB0",
+                "AP WR A:3 ACK:1 OK Data:hC5ACCE55",
+                "AP WR A:1 ACK:1 OK Data:hE0000E80",
+                "AP WR A:3 ACK:1 OK Data:h00000000",
+                "AP WR A:1 ACK:1 OK Data:hE0000E00",
+                "AP WR A:3 ACK:1 OK Data:h0000010F",
+                "AP WR A:1 ACK:1 OK Data:hE0000E80",
+                "AP WR A:3 ACK:1 OK Data:h0001000D",
+            ],
+            |ap, direction, access| tracker.push(ap, direction, access),
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], SwoEvent::ItmDisabled);
+        let SwoEvent::Configured(configuration) = events[1] else {
+            panic!("{:?}", events[1]);
+        };
+        assert_eq!(configuration.protocol, Some(PinProtocol::Nrz));
+        assert_eq!(configuration.prescaler, Some(48));
+        assert_eq!(configuration.enabled_ports(), Some(vec![0, 1, 2, 3, 8]));
+        assert_eq!(
+            events[1].to_string(),
+            "SWO configured: NRZ, prescaler 48, formatter off, stimulus ports 0-3,8"
+        );
+    }
+}
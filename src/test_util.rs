@@ -0,0 +1,33 @@
+//! Helpers shared by the unit tests
+
+use crate::decoder::{Decoder, MemoryAccess};
+use crate::dp::ApAddress;
+use crate::memory::memory_access;
+use crate::parser::{self, Direction};
+
+/// The memory accesses of log lines, each with the one-based number of the
+/// line performing it
+pub fn memory_accesses(lines: &[&str]) -> Vec<(usize, ApAddress, Direction, MemoryAccess)> {
+    let mut decoder = Decoder::new();
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let decoded = decoder.decode(parser::parse(line).unwrap().1);
+            let (ap, direction, access) = memory_access(&decoded)?;
+            Some((i + 1, ap, direction, access))
+        })
+        .collect()
+}
+
+/// Decode log lines and feed their memory accesses to a tracker, returns what
+/// the tracker reported
+pub fn track<T>(
+    lines: &[&str],
+    mut push: impl FnMut(ApAddress, Direction, &MemoryAccess) -> Option<T>,
+) -> Vec<T> {
+    memory_accesses(lines)
+        .into_iter()
+        .filter_map(|(_, ap, direction, access)| push(ap, direction, &access))
+        .collect()
+}